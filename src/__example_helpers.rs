@@ -166,7 +166,10 @@ impl StubXConn for ExampleXConn {
     fn mock_wait_for_event(&self) -> Result<XEvent> {
         let mut remaining = self.events.replace(vec![]);
         if remaining.is_empty() {
-            return Ok(XEvent::KeyPress(EXIT_CODE));
+            return Ok(XEvent::KeyPress {
+                code: EXIT_CODE,
+                root: 0,
+            });
         }
         let next = remaining.remove(0);
         self.events.set(remaining);
@@ -180,8 +183,8 @@ impl StubXConn for ExampleXConn {
             .collect()
     }
 
-    fn mock_focused_client(&self) -> WinId {
-        self.focused.get()
+    fn mock_focused_client(&self) -> Option<WinId> {
+        Some(self.focused.get())
     }
 
     fn mock_focus_client(&self, id: WinId) {
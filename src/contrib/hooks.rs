@@ -141,13 +141,15 @@ pub enum SpawnRule<'a> {
     ClassName(&'a str, usize),
     /// Target a client by WM_NAME
     WMName(&'a str, usize),
+    /// Target a client by _NET_WM_WINDOW_TYPE
+    WindowType(&'a str, usize),
 }
 
 /**
  * Move clients with a matching WM_NAME to a target workspace when they are spawned.
  *
- * The Strings used to identify the clients that should be moved are their WM_NAME
- * and WM_CLASS X11 properties.
+ * The Strings used to identify the clients that should be moved are their WM_NAME,
+ * WM_CLASS and _NET_WM_WINDOW_TYPE X11 properties.
  * ```
  * # #[macro_use] extern crate penrose; fn main() {
  * use penrose::contrib::hooks::{SpawnRule, ClientSpawnRules};
@@ -155,6 +157,7 @@ pub enum SpawnRule<'a> {
  * let my_hook = ClientSpawnRules::new(vec![
  *     SpawnRule::ClassName("xterm-256color" , 3),
  *     SpawnRule::WMName("Firefox Developer Edition" , 7),
+ *     SpawnRule::WindowType("_NET_WM_WINDOW_TYPE_DIALOG" , 9),
  * ]);
  * # }
  */
@@ -162,6 +165,7 @@ pub enum SpawnRule<'a> {
 pub struct ClientSpawnRules {
     class_rules: HashMap<String, usize>,
     name_rules: HashMap<String, usize>,
+    type_rules: HashMap<String, usize>,
 }
 
 impl ClientSpawnRules {
@@ -169,17 +173,20 @@ impl ClientSpawnRules {
     pub fn new(rules: Vec<SpawnRule<'_>>) -> Box<Self> {
         let mut class_rules = HashMap::new();
         let mut name_rules = HashMap::new();
+        let mut type_rules = HashMap::new();
 
         for rule in rules.into_iter() {
             match rule {
                 SpawnRule::ClassName(s, i) => class_rules.insert(s.into(), i),
                 SpawnRule::WMName(s, i) => name_rules.insert(s.into(), i),
+                SpawnRule::WindowType(s, i) => type_rules.insert(s.into(), i),
             };
         }
 
         Box::new(Self {
             class_rules,
             name_rules,
+            type_rules,
         })
     }
 }
@@ -192,6 +199,8 @@ impl<X: XConn> Hook<X> for ClientSpawnRules {
             c.set_workspace(*wix);
         } else if let Some(wix) = self.name_rules.get(c.wm_name()) {
             c.set_workspace(*wix);
+        } else if let Some(wix) = self.type_rules.get(c.wm_type()) {
+            c.set_workspace(*wix);
         }
 
         Ok(())
@@ -13,10 +13,14 @@ pub struct Client {
     id: WinId,
     wm_name: String,
     wm_class: String,
+    #[cfg_attr(feature = "serde", serde(default))]
+    wm_type: String,
     workspace: usize,
     // state flags
     pub(crate) floating: bool,
     pub(crate) fullscreen: bool,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) iconified: bool,
     pub(crate) mapped: bool,
     pub(crate) wm_managed: bool,
 }
@@ -34,9 +38,11 @@ impl Client {
             id,
             wm_name,
             wm_class,
+            wm_type: String::new(),
             workspace,
             floating,
             fullscreen: false,
+            iconified: false,
             mapped: false,
             wm_managed: true,
         }
@@ -57,11 +63,21 @@ impl Client {
         &self.wm_name
     }
 
+    /// The first _NET_WM_WINDOW_TYPE of this client (empty if not set)
+    pub fn wm_type(&self) -> &str {
+        &self.wm_type
+    }
+
     /// Whether or not this client is currently fullscreen
     pub fn is_fullscreen(&self) -> bool {
         self.fullscreen
     }
 
+    /// Whether or not this client is currently iconified (minimized)
+    pub fn is_iconified(&self) -> bool {
+        self.iconified
+    }
+
     /// The current workspace index that this client is showing on
     pub fn workspace(&self) -> usize {
         self.workspace
@@ -81,6 +97,10 @@ impl Client {
         self.wm_name = name.into()
     }
 
+    pub(crate) fn set_wm_type(&mut self, wm_type: impl Into<String>) {
+        self.wm_type = wm_type.into()
+    }
+
     /// The WM_CLASS of the window that this Client is tracking
     pub fn class(&self) -> &str {
         &self.wm_class
@@ -45,6 +45,10 @@ pub enum WinConfig {
     Position(Region),
     /// Mark this window as stacking on top of its peers
     StackAbove,
+    /// Mark this window as stacking directly above the given sibling
+    StackAboveSibling(WinId),
+    /// Mark this window as stacking below its peers
+    StackBelow,
 }
 
 /// Window attributes for an X11 client window (not all are curently implemented)
@@ -55,10 +59,38 @@ pub enum WinAttr {
     BorderColor(u32),
     /// Set the pre-defined client event mask
     ClientEventMask,
+    /// Set an arbitrary event mask, replacing any previously selected events
+    EventMask(u32),
     /// Set the pre-defined root event mask
     RootEventMask,
 }
 
+/// Raw pixel data captured from a region of the screen
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ImageData {
+    /// The width of the image in pixels
+    pub w: u32,
+    /// The height of the image in pixels
+    pub h: u32,
+    /// Pixel data as packed RGBA bytes, row by row from the top left corner
+    pub data: Vec<u8>,
+}
+
+/// Identifying details of the running X server
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ServerInfo {
+    /// The vendor string reported by the server (e.g. "The X.Org Foundation")
+    pub vendor: String,
+    /// The vendor specific release number of the server
+    pub release: u32,
+    /// The major version of the X protocol supported by the server
+    pub major: u16,
+    /// The minor version of the X protocol supported by the server
+    pub minor: u16,
+}
+
 /// A relative position along the horizontal and vertical axes
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
@@ -73,6 +105,96 @@ pub enum RelativePosition {
     Below,
 }
 
+/// Where a window was placed in the stack after being circulated
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum CirculatePlace {
+    /// The window is now on top of all of its siblings
+    Top,
+    /// The window is now below all of its siblings
+    Bottom,
+}
+
+/// The connection state of a physical output (monitor)
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum OutputConnection {
+    /// A monitor is connected to the output
+    Connected,
+    /// No monitor is connected to the output
+    Disconnected,
+    /// The X server is unable to determine if a monitor is connected
+    Unknown,
+}
+
+/// A display mode supported by a physical output (monitor)
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct ModeInfo {
+    /// The X server ID for this mode
+    pub id: u32,
+    /// The width of this mode in pixels
+    pub w: u32,
+    /// The height of this mode in pixels
+    pub h: u32,
+    /// The refresh rate of this mode in millihertz (if it could be determined)
+    pub refresh_mhz: Option<u32>,
+}
+
+/// A DPMS (Display Power Management Signaling) power level for connected monitors
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum DpmsMode {
+    /// Monitors are on
+    On,
+    /// Monitors are in standby (fastest to recover)
+    Standby,
+    /// Monitors are suspended
+    Suspend,
+    /// Monitors are off (slowest to recover)
+    Off,
+}
+
+/// The current DPMS state of the X server
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct DpmsInfo {
+    /// Whether or not DPMS is enabled
+    pub enabled: bool,
+    /// The current power level of connected monitors
+    pub mode: DpmsMode,
+}
+
+/// The gamma correction lookup tables for a physical output (monitor).
+///
+/// Each channel maps an input intensity (the index) to an output intensity in the range
+/// `0..=u16::MAX`. All three channels must be the length reported by the X server for the
+/// output being modified.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GammaRamp {
+    /// The lookup table for the red channel
+    pub red: Vec<u16>,
+    /// The lookup table for the green channel
+    pub green: Vec<u16>,
+    /// The lookup table for the blue channel
+    pub blue: Vec<u16>,
+}
+
+/// The rotation of a physical output (monitor), measured anti-clockwise
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Rotation {
+    /// No rotation
+    Normal,
+    /// Rotated 90 degrees (portrait, top of the output on the left)
+    Left,
+    /// Rotated 180 degrees (upside down)
+    Inverted,
+    /// Rotated 270 degrees (portrait, top of the output on the right)
+    Right,
+}
+
 /// An x,y coordinate pair
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
@@ -90,6 +212,37 @@ impl Point {
     }
 }
 
+/// The current position of the pointer along with the state of the mouse buttons and keyboard
+/// modifiers as reported by the X server.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct PointerState {
+    /// The absolute position of the pointer relative to the root window
+    pub position: Point,
+    /// The mask of mouse buttons currently held (X11 `Button1Mask` through `Button5Mask`)
+    pub buttons: u16,
+    /// The mask of keyboard modifiers currently held (X11 `ShiftMask` through `Mod5Mask`)
+    pub mods: u16,
+}
+
+impl PointerState {
+    /// Whether or not the given mouse button (1-5) is currently held
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use penrose::core::data_types::{Point, PointerState};
+    ///
+    /// let state = PointerState { position: Point::new(0, 0), buttons: 1 << 8, mods: 0 };
+    ///
+    /// assert!(state.button_held(1));
+    /// assert!(!state.button_held(3));
+    /// ```
+    pub fn button_held(&self, button: u8) -> bool {
+        (1..=5).contains(&button) && self.buttons & (1 << (7 + button)) != 0
+    }
+}
+
 /* Argument enums */
 
 /// Increment / decrement a value
@@ -188,6 +341,29 @@ impl Region {
         }
     }
 
+    /// Create a new [Region] inside of this one with an offset and size given as fractions
+    /// (0.0 to 1.0) of the width and height of `self`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use penrose::core::data_types::Region;
+    ///
+    /// let r = Region::new(100, 0, 800, 600);
+    ///
+    /// assert_eq!(r.relative_sub_region(0.0, 0.0, 0.5, 0.5), Region::new(100, 0, 400, 300));
+    /// assert_eq!(r.relative_sub_region(0.25, 0.5, 0.5, 0.5), Region::new(300, 300, 400, 300));
+    /// ```
+    pub fn relative_sub_region(&self, x_pct: f64, y_pct: f64, w_pct: f64, h_pct: f64) -> Self {
+        let (w, h) = (self.w as f64, self.h as f64);
+        Self {
+            x: self.x + (w * x_pct).floor() as u32,
+            y: self.y + (h * y_pct).floor() as u32,
+            w: (w * w_pct).floor() as u32,
+            h: (h * h_pct).floor() as u32,
+        }
+    }
+
     /// Check whether this Region contains `other` as a sub-Region
     ///
     /// # Examples
@@ -312,6 +488,70 @@ impl Region {
             .collect()
     }
 
+    /// The center point of this region.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use penrose::core::data_types::{Point, Region};
+    ///
+    /// let r = Region::new(10, 20, 30, 40);
+    ///
+    /// assert_eq!(r.midpoint(), Point::new(25, 40));
+    /// ```
+    pub fn midpoint(&self) -> Point {
+        Point::new(self.x + self.w / 2, self.y + self.h / 2)
+    }
+
+    /// Find the index of the [Region] in `candidates` whose midpoint lies in the requested
+    /// direction from the midpoint of this region and is closest to it.
+    ///
+    /// This does not require the candidates to be laid out in a grid: the nearest midpoint in
+    /// the requested direction is always selected.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use penrose::core::data_types::{Region, RelativePosition};
+    ///
+    /// let screens = vec![
+    ///     Region::new(0, 0, 1920, 1080),
+    ///     Region::new(1920, 0, 1920, 1080),
+    ///     Region::new(3840, 200, 1080, 1920),
+    /// ];
+    ///
+    /// let r = screens[1];
+    /// assert_eq!(r.nearest_in_direction(RelativePosition::Left, &screens), Some(0));
+    /// assert_eq!(r.nearest_in_direction(RelativePosition::Right, &screens), Some(2));
+    /// assert_eq!(r.nearest_in_direction(RelativePosition::Above, &screens), None);
+    /// ```
+    pub fn nearest_in_direction(
+        &self,
+        direction: RelativePosition,
+        candidates: &[Region],
+    ) -> Option<usize> {
+        let p = self.midpoint();
+
+        candidates
+            .iter()
+            .enumerate()
+            .filter(|(_, r)| {
+                let q = r.midpoint();
+                match direction {
+                    RelativePosition::Left => q.x < p.x,
+                    RelativePosition::Right => q.x > p.x,
+                    RelativePosition::Above => q.y < p.y,
+                    RelativePosition::Below => q.y > p.y,
+                }
+            })
+            .min_by_key(|(_, r)| {
+                let q = r.midpoint();
+                let (dx, dy) = (q.x as i64 - p.x as i64, q.y as i64 - p.y as i64);
+                dx * dx + dy * dy
+            })
+            .map(|(i, _)| i)
+    }
+
     /// Divides this region into two columns where the first has the given width.
     ///
     /// # Errors
@@ -24,20 +24,30 @@ pub struct WmState<'a> {
 /// [2]: crate::core::xconnection::XConn
 #[derive(Debug, Clone)]
 pub enum EventAction {
+    /// The _NET_SUPPORTING_WM_CHECK window was destroyed and needs to be recreated
+    CheckWindowLost,
     /// An X window gained focus
     ClientFocusLost(WinId),
     /// An X window lost focus
     ClientFocusGained(WinId),
     /// An X window had its WM_NAME or _NET_WM_NAME property changed
     ClientNameChanged(WinId, bool),
+    /// An X window had its WM_NORMAL_HINTS property changed
+    ClientSizeHintsChanged(WinId),
     /// Move the given client to the workspace at the given index
     ClientToWorkspace(WinId, usize),
+    /// The _NET_DESKTOP_NAMES property of the root window has been changed
+    DesktopNamesChanged(WinId),
     /// An X window was destroyed
     DestroyClient(WinId),
     /// Screens should be redetected
     DetectScreens,
+    /// A client is requesting to be iconified (minimized)
+    IconifyClient(WinId),
     /// A new X window needs to be mapped
     MapWindow(WinId),
+    /// An X window that was suspending layouts has been unmapped
+    ResumeLayout(WinId),
     /// A client is requesting to be moved: honoured if the client is floating
     MoveClientIfFloating(WinId, Region),
     /// A grabbed keybinding was triggered
@@ -48,8 +58,14 @@ pub enum EventAction {
     SetActiveClient(WinId),
     /// The active workspace should be set to this index
     SetActiveWorkspace(usize),
+    /// A client is requesting that _NET_FRAME_EXTENTS be set before it is mapped
+    SetFrameExtents(WinId),
     /// The active screen should be set based on point location
     SetScreenFromPoint(Option<Point>),
+    /// Part of a _NET_STARTUP_INFO message was received (true if this is the first part)
+    StartupInfo(WinId, Vec<u8>, bool),
+    /// An override-redirect window was mapped: layouts should be suspended if it covers a screen
+    SuspendLayout(WinId),
     /// An X window should be set fullscreen
     ToggleClientFullScreen(WinId, bool),
     /// An unknown property was changed on an X window
@@ -59,16 +75,33 @@ pub enum EventAction {
 pub fn process_next_event(event: XEvent, state: WmState<'_>) -> Vec<EventAction> {
     match event {
         // Direct 1-n mappings of XEvents -> EventActions
+        XEvent::CheckWindowLost => vec![EventAction::CheckWindowLost],
+        XEvent::CirculateNotify { .. } => vec![], // we do not currently track stacking order
         XEvent::Destroy { id } => vec![EventAction::DestroyClient(id)],
         XEvent::Expose { .. } => vec![], // TODO: work out if this needs handling in the WindowManager
-        XEvent::KeyPress(code) => vec![EventAction::RunKeyBinding(code)],
+        XEvent::KeyPress { code, .. } => vec![EventAction::RunKeyBinding(code)],
+        XEvent::KeyboardLayoutChange { .. } => vec![],
+        XEvent::KeyboardStateChange { .. } => vec![],
         XEvent::Leave { id, rpt, .. } => vec![
             EventAction::ClientFocusLost(id),
             EventAction::SetScreenFromPoint(Some(rpt)),
         ],
+        XEvent::MapNotify {
+            id,
+            override_redirect,
+        } => {
+            if override_redirect {
+                vec![EventAction::SuspendLayout(id)]
+            } else {
+                vec![]
+            }
+        }
         XEvent::MouseEvent(evt) => vec![EventAction::RunMouseBinding(evt)],
         XEvent::RandrNotify => vec![EventAction::DetectScreens],
+        XEvent::OutputChange { .. } => vec![EventAction::DetectScreens],
         XEvent::ScreenChange => vec![EventAction::SetScreenFromPoint(None)],
+        XEvent::SelectionClear { .. } => vec![], // handled by whatever owned the selection
+        XEvent::UnmapNotify { id } => vec![EventAction::ResumeLayout(id)],
 
         // Require processing based on current WindowManager state
         XEvent::ClientMessage { id, dtype, data } => {
@@ -97,6 +130,11 @@ fn process_client_message(
         Ok(Atom::NetActiveWindow) => vec![EventAction::SetActiveClient(id)],
         Ok(Atom::NetCurrentDesktop) => vec![EventAction::SetActiveWorkspace(data[0])],
         Ok(Atom::NetWmDesktop) => vec![EventAction::ClientToWorkspace(id, data[0])],
+        Ok(Atom::NetRequestFrameExtents) => vec![EventAction::SetFrameExtents(id)],
+        Ok(Atom::NetStartupInfoBegin) => vec![EventAction::StartupInfo(id, bytes(data), true)],
+        Ok(Atom::NetStartupInfo) => vec![EventAction::StartupInfo(id, bytes(data), false)],
+        // ICCCM IconicState == 3
+        Ok(Atom::WmChangeState) if data[0] == 3 => vec![EventAction::IconifyClient(id)],
         Ok(Atom::NetWmState) if data[1..3].contains(&state.full_screen_atom) => {
             // _NET_WM_STATE_ADD == 1, _NET_WM_STATE_TOGGLE == 2
             let should_fullscreen = [1, 2].contains(&data[0]);
@@ -107,6 +145,11 @@ fn process_client_message(
     }
 }
 
+// Startup notification messages are sent as format 8 client messages
+fn bytes(data: &[usize]) -> Vec<u8> {
+    data.iter().map(|&b| b as u8).collect()
+}
+
 fn process_configure_notify(_id: WinId, _r: Region, is_root: bool) -> Vec<EventAction> {
     if is_root {
         vec![EventAction::DetectScreens]
@@ -151,6 +194,8 @@ fn process_property_notify(id: WinId, atom: String, is_root: bool) -> Vec<EventA
         Ok(a) if a == Atom::WmName || a == Atom::NetWmName => {
             vec![EventAction::ClientNameChanged(id, is_root)]
         }
+        Ok(Atom::WmNormalHints) if !is_root => vec![EventAction::ClientSizeHintsChanged(id)],
+        Ok(Atom::NetDesktopNames) if is_root => vec![EventAction::DesktopNamesChanged(id)],
         _ => vec![EventAction::UnknownPropertyChange(id, atom, is_root)],
     }
 }
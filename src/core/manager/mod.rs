@@ -9,7 +9,7 @@ use crate::{
         ring::{Direction, InsertPoint, Ring, Selector},
         screen::Screen,
         workspace::Workspace,
-        xconnection::{Atom, XConn},
+        xconnection::{Atom, Prop, XConn},
     },
     ErrorHandler, PenroseError, Result,
 };
@@ -77,6 +77,12 @@ pub struct WindowManager<X: XConn> {
     previous_workspace: usize,
     client_insert_point: InsertPoint,
     focused_client: Option<WinId>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    layout_suspended_by: Option<WinId>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    startup_messages: HashMap<WinId, Vec<u8>>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    startup_workspaces: HashMap<String, usize>,
     running: bool,
     #[cfg_attr(feature = "serde", serde(skip, default = "logging_error_handler"))]
     error_handler: ErrorHandler,
@@ -96,6 +102,8 @@ impl<X: XConn> fmt::Debug for WindowManager<X> {
             .field("previous_workspace", &self.previous_workspace)
             .field("client_insert_point", &self.client_insert_point)
             .field("focused_client", &self.focused_client)
+            .field("layout_suspended_by", &self.layout_suspended_by)
+            .field("startup_workspaces", &self.startup_workspaces)
             .field("running", &self.running)
             .finish()
     }
@@ -147,6 +155,9 @@ impl<X: XConn> WindowManager<X> {
             hooks: Cell::new(hooks),
             client_insert_point: InsertPoint::First,
             focused_client: None,
+            layout_suspended_by: None,
+            startup_messages: HashMap::new(),
+            startup_workspaces: HashMap::new(),
             running: false,
             hydrated: true,
             error_handler,
@@ -289,22 +300,34 @@ impl<X: XConn> WindowManager<X> {
     ) -> Result<()> {
         debug!("Handling event action: {:?}", action);
         match action {
+            EventAction::CheckWindowLost => self.restore_check_window()?,
             EventAction::ClientFocusGained(id) => self.client_gained_focus(id),
             EventAction::ClientFocusLost(id) => self.client_lost_focus(id),
             EventAction::ClientNameChanged(id, is_root) => self.client_name_changed(id, is_root)?,
+            EventAction::ClientSizeHintsChanged(id) => self.client_size_hints_changed(id),
             EventAction::ClientToWorkspace(id, wix) => self.move_client_to_workspace(id, wix)?,
+            EventAction::DesktopNamesChanged(root) => self.sync_workspace_names(root),
             EventAction::DestroyClient(id) => self.remove_client(id),
             EventAction::DetectScreens => {
                 run_hooks!(randr_notify, self,);
                 self.detect_screens()?
             }
+            EventAction::IconifyClient(id) => self.set_iconified(id, true),
             EventAction::MapWindow(id) => self.handle_map_request(id)?,
             EventAction::MoveClientIfFloating(id, r) => self.handle_move_if_floating(id, r)?,
+            EventAction::ResumeLayout(id) => self.resume_layout(id),
             EventAction::RunKeyBinding(k) => self.run_key_binding(k, key_bindings),
             EventAction::RunMouseBinding(e) => self.run_mouse_binding(e, mouse_bindings),
             EventAction::SetActiveClient(id) => self.set_active_client(id)?,
             EventAction::SetActiveWorkspace(wix) => self.focus_workspace(&Selector::Index(wix))?,
+            EventAction::SetFrameExtents(id) => self.set_frame_extents(id)?,
             EventAction::SetScreenFromPoint(p) => self.set_screen_from_point(p),
+            EventAction::StartupInfo(id, data, begin) => self.handle_startup_info(id, data, begin),
+            EventAction::SuspendLayout(id) => {
+                if self.conn.is_fullscreen_override(id) {
+                    self.suspend_layout(id);
+                }
+            }
             EventAction::ToggleClientFullScreen(id, should_fullscreen) => {
                 self.set_fullscreen(id, should_fullscreen);
             }
@@ -481,6 +504,37 @@ impl<X: XConn> WindowManager<X> {
         Ok(())
     }
 
+    // Clients such as terminals may change their size hints at runtime (e.g. on font size
+    // changes) so we re-layout their workspace to pick up the new hints.
+    fn client_size_hints_changed(&mut self, id: WinId) {
+        if let Some(wix) = self.client_map.get(&id).map(|c| c.workspace()) {
+            self.apply_layout(wix);
+        }
+    }
+
+    // Desktop names may be updated by external tools (such as pagers) so we need to pick up any
+    // changes to keep our workspace names in sync.
+    fn sync_workspace_names(&mut self, root: WinId) {
+        let names = match self.conn.get_prop(root, Atom::NetDesktopNames.as_ref()) {
+            Ok(Prop::UTF8String(names)) => names,
+            _ => return,
+        };
+
+        let mut changed = false;
+        for (ws, name) in self.workspaces.iter_mut().zip(names.iter()) {
+            if ws.name() != name {
+                ws.set_name(name);
+                changed = true;
+            }
+        }
+
+        if changed {
+            let vec_names = self.workspaces.vec_map(|w| w.name().to_string());
+            let names = str_slice!(vec_names);
+            run_hooks!(workspaces_updated, self, names, self.active_ws_index());
+        }
+    }
+
     // The given window ID has been destroyed so remove our internal state referencing it.
     fn remove_client(&mut self, id: WinId) {
         if let Some(client) = self.client_map.remove(&id) {
@@ -602,6 +656,15 @@ impl<X: XConn> WindowManager<X> {
             self.active_ws_index(),
             floating,
         );
+        client.set_wm_type(props.ty);
+
+        // Place the client on the workspace requested by the launcher if we know about it
+        let startup_wix = util::startup_id(&self.conn, id)
+            .and_then(|sid| self.startup_workspaces.remove(&sid))
+            .filter(|&wix| wix < self.workspaces.len());
+        if let Some(wix) = startup_wix {
+            client.set_workspace(wix);
+        }
 
         // Run hooks to allow them to modify the client
         run_hooks!(new_client, self, &mut client);
@@ -640,6 +703,8 @@ impl<X: XConn> WindowManager<X> {
     fn handle_move_if_floating(&mut self, id: WinId, r: Region) -> Result<()> {
         if let Some(client) = self.client_map.get(&id) {
             if client.floating {
+                // Only honour the most recent request if the client is sending them rapidly
+                let r = self.conn.latest_configure_request(id).unwrap_or(r);
                 debug!("Repositioning floating window: id={} r={:?}", id, r);
                 let bpx = self.config.border_px;
                 self.conn.position_window(id, r, bpx, true);
@@ -682,13 +747,23 @@ impl<X: XConn> WindowManager<X> {
         }
     }
 
+    // Taskbars restore iconified clients by requesting that they become the active window
     fn set_active_client(&mut self, id: WinId) -> Result<()> {
+        self.set_iconified(id, false);
         self.focus_client(&Selector::WinId(id))
             .map_err(|_| PenroseError::UnknownClient(id))?;
 
         Ok(())
     }
 
+    // Our only window decoration is the border so that is all we report as the frame extents
+    // (left, right, top, bottom) for clients that ask before they are mapped.
+    fn set_frame_extents(&self, id: WinId) -> Result<()> {
+        let b = self.config.border_px;
+        self.conn
+            .set_cardinal_prop(id, Atom::NetFrameExtents.as_ref(), &[b, b, b, b])
+    }
+
     // Set the active [Screen] based on an (x, y) [Point]. If point is None then we set
     // based on the current cursor position instead.
     fn set_screen_from_point(&mut self, point: Option<Point>) {
@@ -719,11 +794,132 @@ impl<X: XConn> WindowManager<X> {
         None
     }
 
+    // Something other than us destroyed our _NET_SUPPORTING_WM_CHECK window so we need to
+    // create a new one and reset the EWMH properties that point to it.
+    fn restore_check_window(&mut self) -> Result<()> {
+        warn!("_NET_SUPPORTING_WM_CHECK window was destroyed: recreating it");
+        self.conn.recreate_check_window()?;
+        let vec_names = self.workspaces.vec_map(|w| w.name().to_string());
+        self.conn.set_wm_properties(str_slice!(vec_names));
+        self.conn.set_current_workspace(self.active_ws_index());
+        self.update_x_known_clients();
+
+        Ok(())
+    }
+
+    // Startup notification messages may be split over multiple client messages so we buffer
+    // them per sending window until we see the null terminator. We only track the requested
+    // desktop for each launch so that we can place the client once it is mapped.
+    fn handle_startup_info(&mut self, id: WinId, data: Vec<u8>, begin: bool) {
+        if begin {
+            self.startup_messages.insert(id, vec![]);
+        }
+
+        let buf = match self.startup_messages.get_mut(&id) {
+            Some(buf) => buf,
+            None => return, // continuation of a message we never saw the start of
+        };
+        buf.extend(data);
+
+        let end = match buf.iter().position(|&b| b == 0) {
+            Some(end) => end,
+            None => return,
+        };
+        let buf = self.startup_messages.remove(&id).unwrap_or_default();
+        let msg = String::from_utf8_lossy(&buf[..end]);
+        debug!("got startup notification message: {}", msg);
+
+        if let Some((kind, fields)) = util::parse_startup_message(&msg) {
+            let sid = match fields.get("ID") {
+                Some(sid) => sid.clone(),
+                None => return,
+            };
+            match kind.as_ref() {
+                "new" | "change" => {
+                    if let Some(wix) = fields.get("DESKTOP").and_then(|d| d.parse().ok()) {
+                        self.startup_workspaces.insert(sid, wix);
+                    }
+                }
+                "remove" => {
+                    self.startup_workspaces.remove(&sid);
+                }
+                _ => (),
+            }
+        }
+    }
+
+    // Iconified clients are unmapped and skipped when laying out their workspace until they
+    // are restored. Restored clients on hidden workspaces are left unmapped until their
+    // workspace is next displayed.
+    fn set_iconified(&mut self, id: WinId, should_iconify: bool) {
+        let client = match self.client_map.get_mut(&id) {
+            Some(c) if c.iconified != should_iconify => c,
+            _ => return,
+        };
+
+        client.iconified = should_iconify;
+        let wix = client.workspace();
+        if should_iconify {
+            client.mapped = false;
+            self.conn.iconify(id);
+            if self.focused_client == Some(id) {
+                self.focus_next_visible_client(wix, id);
+            }
+        } else {
+            self.conn.deiconify(id);
+            if self.visible_workspaces().contains(&wix) {
+                util::map_window_if_needed(&self.conn, self.client_map.get_mut(&id));
+            }
+        }
+        self.apply_layout(wix);
+    }
+
+    // Pass focus from a client that is being hidden to the next client on the same workspace
+    // that is still visible, if there is one.
+    fn focus_next_visible_client(&mut self, wix: usize, id: WinId) {
+        let ids = match self.workspaces.get(wix) {
+            Some(ws) => ws.client_ids(),
+            None => return,
+        };
+        let pos = ids.iter().position(|&c| c == id).unwrap_or(0);
+        let next = ids
+            .iter()
+            .cycle()
+            .skip(pos + 1)
+            .take(ids.len())
+            .find(|c| matches!(self.client_map.get(c), Some(c) if !c.iconified));
+
+        match next {
+            Some(&next) => self.client_gained_focus(next),
+            None => self.client_lost_focus(id),
+        }
+    }
+
     /*
      * Common mid level actions that make up larger event response handlers.
      */
 
+    // Full screen override-redirect windows (screen lockers etc) are not managed by us so we
+    // hold off on laying out any workspaces until they are unmapped again.
+    fn suspend_layout(&mut self, id: WinId) {
+        debug!("suspending layouts while {} is mapped", id);
+        self.layout_suspended_by = Some(id);
+    }
+
+    fn resume_layout(&mut self, id: WinId) {
+        if self.layout_suspended_by == Some(id) {
+            debug!("resuming layouts now that {} is unmapped", id);
+            self.layout_suspended_by = None;
+            self.layout_visible();
+        }
+    }
+
     fn apply_layout(&mut self, wix: usize) {
+        if let Some(id) = self.layout_suspended_by {
+            debug!("not laying out workspace {}: suspended by {}", wix, id);
+            return;
+        }
+
         debug!("Attempting to layout workspace {}", wix);
         let ws = match self.workspaces.get(wix) {
             Some(ws) => ws,
@@ -1348,6 +1544,7 @@ impl<X: XConn> WindowManager<X> {
                     // re-apply layouts as screen dimensions may differ
                     self.apply_layout(active);
                     self.apply_layout(index);
+                    self.conn.set_current_workspace(index);
 
                     let ws = self.workspaces.get(index);
                     if let Some(id) = ws.and_then(|ws| ws.focused_client()) {
@@ -1363,20 +1560,19 @@ impl<X: XConn> WindowManager<X> {
             // target not currently displayed so unmap what we currently have
             // displayed and replace it with the target workspace
             if let Some(ws) = self.workspaces.get(active) {
-                ws.client_ids().iter().for_each(|id| {
-                    util::unmap_window_if_needed(&self.conn, self.client_map.get_mut(id))
-                });
+                util::unmap_windows_if_needed(&self.conn, &ws.client_ids(), &mut self.client_map);
             }
 
             if let Some(ws) = self.workspaces.get(index) {
-                ws.client_ids().iter().for_each(|id| {
-                    util::map_window_if_needed(&self.conn, self.client_map.get_mut(id))
-                });
+                util::map_windows_if_needed(&self.conn, &ws.client_ids(), &mut self.client_map);
             }
 
             self.screens.focused_mut_unchecked().wix = index;
             self.apply_layout(index);
             self.conn.set_current_workspace(index);
+            // Make sure that the server has applied all of the above before we continue so that
+            // we don't show a partially drawn workspace
+            self.conn.sync();
 
             let ws = self.workspaces.get(index);
             if let Some(id) = ws.and_then(|ws| ws.focused_client()) {
@@ -1526,7 +1722,10 @@ impl<X: XConn> WindowManager<X> {
     /// # example(manager).unwrap();
     /// ```
     pub fn kill_client(&mut self) -> Result<()> {
-        let id = self.conn.focused_client();
+        let id = match self.conn.focused_client() {
+            Some(id) => id,
+            None => return Ok(()),
+        };
         let del = Atom::WmDeleteWindow.as_ref();
         if let Err(e) = self.conn.send_client_event(id, del) {
             error!("Error killing client: {}", e);
@@ -2523,6 +2722,243 @@ mod tests {
         assert!(wm.workspaces[0].len() == 1);
     }
 
+    #[test]
+    fn fullscreen_override_windows_suspend_layout_until_unmapped() {
+        let mut wm = wm_with_mock_conn(vec![], vec![]);
+
+        wm.suspend_layout(99);
+        assert_eq!(wm.layout_suspended_by, Some(99));
+
+        wm.resume_layout(42); // not the suspending window
+        assert_eq!(wm.layout_suspended_by, Some(99));
+
+        wm.resume_layout(99);
+        assert_eq!(wm.layout_suspended_by, None);
+    }
+
+    struct FullscreenOverrideXConn;
+
+    impl StubXConn for FullscreenOverrideXConn {
+        fn mock_current_outputs(&self) -> Vec<Screen> {
+            vec![Screen::new(Region::new(0, 0, 800, 600), 0)]
+        }
+
+        fn mock_is_fullscreen_override(&self, id: WinId) -> bool {
+            id == 99
+        }
+    }
+
+    fn map_notify<X: XConn>(wm: &mut WindowManager<X>, id: WinId, override_redirect: bool) {
+        let event = XEvent::MapNotify {
+            id,
+            override_redirect,
+        };
+        for action in process_next_event(event, wm.current_state()) {
+            wm.handle_event_action(action, &mut HashMap::new(), &mut HashMap::new())
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn override_redirect_map_notify_covering_an_output_suspends_layout() {
+        let conn = FullscreenOverrideXConn;
+        let mut wm = WindowManager::new(Config::default(), conn, vec![], logging_error_handler());
+        wm.init().unwrap();
+
+        map_notify(&mut wm, 99, false);
+        assert_eq!(wm.layout_suspended_by, None);
+
+        map_notify(&mut wm, 42, true); // override-redirect but not covering an output
+        assert_eq!(wm.layout_suspended_by, None);
+
+        map_notify(&mut wm, 99, true);
+        assert_eq!(wm.layout_suspended_by, Some(99));
+    }
+
+    #[test]
+    fn iconified_clients_are_skipped_by_layouts_until_restored() {
+        let mut wm = wm_with_mock_conn(vec![], vec![]);
+        wm.handle_map_request(10).unwrap();
+        wm.handle_map_request(20).unwrap();
+
+        wm.set_iconified(10, true);
+        assert!(wm.client_map[&10].is_iconified());
+        assert!(!wm.client_map[&10].mapped);
+        let arranged = wm.workspaces[0].arrange(Region::new(0, 0, 800, 600), &wm.client_map);
+        let ids: Vec<WinId> = arranged.actions.iter().map(|(id, _)| *id).collect();
+        assert_eq!(ids, vec![20]);
+
+        wm.set_active_client(10).unwrap();
+        assert!(!wm.client_map[&10].is_iconified());
+        assert!(wm.client_map[&10].mapped);
+    }
+
+    struct MapRecordingXConn {
+        maps: Cell<Vec<WinId>>,
+    }
+
+    impl StubXConn for MapRecordingXConn {
+        fn mock_current_outputs(&self) -> Vec<Screen> {
+            vec![Screen::new(Region::new(0, 0, 800, 600), 0)]
+        }
+
+        fn mock_map_window(&self, id: WinId) {
+            let mut v = self.maps.take();
+            v.push(id);
+            self.maps.set(v);
+        }
+    }
+
+    #[test]
+    fn iconified_clients_are_not_remapped_on_workspace_switch() {
+        let conn = MapRecordingXConn {
+            maps: Cell::new(Vec::new()),
+        };
+        let mut wm = WindowManager::new(Config::default(), conn, vec![], logging_error_handler());
+        wm.init().unwrap();
+        wm.handle_map_request(10).unwrap();
+        wm.handle_map_request(20).unwrap();
+        wm.set_iconified(10, true);
+        wm.conn().maps.take();
+
+        wm.focus_workspace(&Selector::Index(1)).unwrap();
+        wm.focus_workspace(&Selector::Index(0)).unwrap();
+
+        assert_eq!(wm.conn().maps.take(), vec![20]);
+        assert!(!wm.client_map[&10].mapped);
+    }
+
+    #[test]
+    fn iconifying_the_focused_client_moves_focus_to_a_visible_client() {
+        let mut wm = wm_with_mock_conn(vec![], vec![]);
+        wm.handle_map_request(10).unwrap();
+        wm.handle_map_request(20).unwrap();
+        wm.handle_map_request(30).unwrap();
+        wm.client_gained_focus(30);
+
+        wm.set_iconified(20, true);
+        assert_eq!(wm.focused_client, Some(30));
+
+        wm.set_iconified(30, true);
+        assert_eq!(wm.focused_client, Some(10));
+        assert_eq!(wm.workspaces[0].focused_client(), Some(10));
+
+        wm.set_iconified(10, true);
+        assert_eq!(wm.focused_client, None);
+    }
+
+    #[derive(Default)]
+    struct BatchRecordingXConn {
+        maps: Cell<Vec<Vec<WinId>>>,
+        unmaps: Cell<Vec<Vec<WinId>>>,
+    }
+
+    impl StubXConn for BatchRecordingXConn {
+        fn mock_current_outputs(&self) -> Vec<Screen> {
+            vec![Screen::new(Region::new(0, 0, 800, 600), 0)]
+        }
+
+        fn mock_map_windows(&self, ids: &[WinId]) {
+            let mut v = self.maps.take();
+            v.push(ids.to_vec());
+            self.maps.set(v);
+        }
+
+        fn mock_unmap_windows(&self, ids: &[WinId]) {
+            let mut v = self.unmaps.take();
+            v.push(ids.to_vec());
+            self.unmaps.set(v);
+        }
+    }
+
+    #[test]
+    fn workspace_switches_map_and_unmap_clients_in_a_single_batch() {
+        let conn = BatchRecordingXConn::default();
+        let mut wm = WindowManager::new(Config::default(), conn, vec![], logging_error_handler());
+        wm.init().unwrap();
+        wm.handle_map_request(10).unwrap();
+        wm.handle_map_request(20).unwrap();
+        wm.conn().maps.take();
+
+        let ids = wm.workspaces[0].client_ids();
+        wm.focus_workspace(&Selector::Index(1)).unwrap();
+        assert_eq!(wm.conn().unmaps.take(), vec![ids.clone()]);
+        assert_eq!(wm.conn().maps.take(), Vec::<Vec<WinId>>::new());
+
+        wm.focus_workspace(&Selector::Index(0)).unwrap();
+        assert_eq!(wm.conn().unmaps.take(), Vec::<Vec<WinId>>::new());
+        assert_eq!(wm.conn().maps.take(), vec![ids]);
+    }
+
+    struct CurrentDesktopXConn {
+        current: Cell<Option<usize>>,
+    }
+
+    impl StubXConn for CurrentDesktopXConn {
+        fn mock_current_outputs(&self) -> Vec<Screen> {
+            test_screens()
+        }
+
+        fn mock_set_current_workspace(&self, wix: usize) {
+            self.current.set(Some(wix));
+        }
+    }
+
+    #[test]
+    fn swapping_workspaces_between_screens_updates_current_desktop() {
+        let conn = CurrentDesktopXConn {
+            current: Cell::new(None),
+        };
+        let mut wm = WindowManager::new(Config::default(), conn, vec![], logging_error_handler());
+        wm.init().unwrap();
+        assert_eq!(wm.screens[1].wix, 1);
+        wm.conn().current.set(None);
+
+        // workspace 1 is already displayed on the second screen so it is swapped onto the first
+        wm.focus_workspace(&Selector::Index(1)).unwrap();
+
+        assert_eq!(wm.screens[0].wix, 1);
+        assert_eq!(wm.screens[1].wix, 0);
+        assert_eq!(wm.conn().current.get(), Some(1));
+    }
+
+    #[test]
+    fn restoring_clients_on_hidden_workspaces_does_not_map_them() {
+        let conn = MapRecordingXConn {
+            maps: Cell::new(Vec::new()),
+        };
+        let mut wm = WindowManager::new(Config::default(), conn, vec![], logging_error_handler());
+        wm.init().unwrap();
+        wm.handle_map_request(10).unwrap();
+        wm.set_iconified(10, true);
+        wm.move_client_to_workspace(10, 1).unwrap();
+        wm.conn().maps.take();
+
+        wm.set_iconified(10, false);
+        assert!(!wm.client_map[&10].is_iconified());
+        assert!(!wm.client_map[&10].mapped);
+        assert_eq!(wm.conn().maps.take(), vec![]);
+
+        wm.focus_workspace(&Selector::Index(1)).unwrap();
+        assert!(wm.client_map[&10].mapped);
+        assert_eq!(wm.conn().maps.take(), vec![10]);
+    }
+
+    #[test]
+    fn startup_info_messages_are_reassembled() {
+        let mut wm = wm_with_mock_conn(vec![], vec![]);
+        let msg = b"new: ID=launcher-42 NAME=\"Some App\" DESKTOP=3\0".to_vec();
+
+        wm.handle_startup_info(5, msg[..20].to_vec(), true);
+        assert!(wm.startup_workspaces.is_empty());
+        wm.handle_startup_info(5, msg[20..].to_vec(), false);
+        assert_eq!(wm.startup_workspaces.get("launcher-42"), Some(&3));
+        assert!(wm.startup_messages.is_empty());
+
+        wm.handle_startup_info(5, b"remove: ID=launcher-42\0".to_vec(), true);
+        assert!(wm.startup_workspaces.is_empty());
+    }
+
     struct ScreenChangingXConn {
         num_screens: Cell<usize>,
     }
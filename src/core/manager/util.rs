@@ -28,9 +28,10 @@ pub(super) fn pad_region(region: &Region, gapless: bool, gap_px: u32, border_px:
     Region::new(x + gpx, y + gpx, w - padding, h - padding)
 }
 
+// Iconified clients are left unmapped until they are explicitly restored
 pub(super) fn map_window_if_needed<X: XConn>(conn: &X, win: Option<&mut Client>) {
     if let Some(c) = win {
-        if !c.mapped {
+        if !c.mapped && !c.iconified {
             c.mapped = true;
             conn.map_window(c.id());
         }
@@ -46,6 +47,47 @@ pub(super) fn unmap_window_if_needed<X: XConn>(conn: &X, win: Option<&mut Client
     }
 }
 
+// Batched versions of the above for mapping / unmapping a whole workspace at once
+pub(super) fn map_windows_if_needed<X: XConn>(
+    conn: &X,
+    ids: &[WinId],
+    client_map: &mut HashMap<WinId, Client>,
+) {
+    let mut to_map = Vec::with_capacity(ids.len());
+    for id in ids {
+        if let Some(c) = client_map.get_mut(id) {
+            if !c.mapped && !c.iconified {
+                c.mapped = true;
+                to_map.push(*id);
+            }
+        }
+    }
+
+    if !to_map.is_empty() {
+        conn.map_windows(&to_map);
+    }
+}
+
+pub(super) fn unmap_windows_if_needed<X: XConn>(
+    conn: &X,
+    ids: &[WinId],
+    client_map: &mut HashMap<WinId, Client>,
+) {
+    let mut to_unmap = Vec::with_capacity(ids.len());
+    for id in ids {
+        if let Some(c) = client_map.get_mut(id) {
+            if c.mapped {
+                c.mapped = false;
+                to_unmap.push(*id);
+            }
+        }
+    }
+
+    if !to_unmap.is_empty() {
+        conn.unmap_windows(&to_unmap);
+    }
+}
+
 pub(super) fn client_str_props<X: XConn>(conn: &X, id: WinId) -> ClientProps {
     ClientProps {
         name: match window_name(conn, id) {
@@ -57,7 +99,7 @@ pub(super) fn client_str_props<X: XConn>(conn: &X, id: WinId) -> ClientProps {
             _ => String::new(),
         },
         ty: match conn.get_prop(id, Atom::NetWmWindowType.as_ref()) {
-            Ok(Prop::UTF8String(strs)) => strs[0].clone(),
+            Ok(Prop::Atom(atoms)) if !atoms.is_empty() => atoms[0].clone(),
             _ => String::new(),
         },
     }
@@ -71,15 +113,27 @@ pub(super) fn position_floating_client<X: XConn>(
 ) -> Result<()> {
     let default_position = conn.window_geometry(id)?;
     let (mut x, mut y, w, h) = default_position.values();
-    let (sx, sy, _, _) = screen_region.values();
+    let (sx, sy, sw, sh) = screen_region.values();
     x = if x < sx { sx } else { x };
     y = if y < sy { sy } else { y };
-    let reg = Region::new(
+    let mut reg = Region::new(
         x + border_px,
         y + border_px,
         w - (2 * border_px),
         h - (2 * border_px),
     );
+
+    // Clients requesting to be maximized occupy the full screen region along that axis
+    let (max_vert, max_horz) = conn.window_wants_maximized(id);
+    if max_horz {
+        reg.x = sx;
+        reg.w = sw.saturating_sub(2 * border_px);
+    }
+    if max_vert {
+        reg.y = sy;
+        reg.h = sh.saturating_sub(2 * border_px);
+    }
+
     conn.position_window(id, reg, border_px, false);
 
     Ok(())
@@ -96,6 +150,45 @@ pub(super) fn window_name<X: XConn>(conn: &X, id: WinId) -> Result<String> {
     }
 }
 
+pub(super) fn startup_id<X: XConn>(conn: &X, id: WinId) -> Option<String> {
+    match conn.get_prop(id, Atom::NetStartupId.as_ref()) {
+        Ok(Prop::UTF8String(strs)) => strs.into_iter().next().filter(|s| !s.is_empty()),
+        _ => None,
+    }
+}
+
+// Startup notification messages are of the form `new: ID=foo NAME="Some App" DESKTOP=2` with
+// values optionally quoted and backslash escaped. Returns the message type and its key/values.
+// See https://specifications.freedesktop.org/startup-notification-spec/startup-notification-latest.txt
+pub(super) fn parse_startup_message(msg: &str) -> Option<(String, HashMap<String, String>)> {
+    let (kind, rest) = msg.split_at(msg.find(':')?);
+    let mut fields = HashMap::new();
+    let mut chars = rest[1..].chars().peekable();
+
+    loop {
+        while matches!(chars.peek(), Some(c) if c.is_whitespace()) {
+            chars.next();
+        }
+        if chars.peek().is_none() {
+            break;
+        }
+
+        let key: String = chars.by_ref().take_while(|&c| c != '=').collect();
+        let (mut val, mut quoted) = (String::new(), false);
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => quoted = !quoted,
+                '\\' => val.extend(chars.next()),
+                c if c.is_whitespace() && !quoted => break,
+                c => val.push(c),
+            }
+        }
+        fields.insert(key, val);
+    }
+
+    Some((kind.trim().to_string(), fields))
+}
+
 pub(super) fn get_screens<X: XConn>(
     conn: &X,
     mut visible_workspaces: Vec<usize>,
@@ -234,13 +327,10 @@ pub(super) fn parse_existing_client<X: XConn>(conn: &X, id: WinId) -> Result<Cli
         _ => 0,
     };
 
-    Ok(Client::new(
-        id,
-        props.name,
-        props.class,
-        wix as usize,
-        false,
-    ))
+    let mut client = Client::new(id, props.name, props.class, wix as usize, false);
+    client.set_wm_type(props.ty);
+
+    Ok(client)
 }
 
 #[cfg(test)]
@@ -267,6 +357,17 @@ mod tests {
         assert_eq!(pad_region(&r, true, g, b), Region::new(0, 0, 194, 94));
     }
 
+    #[test]
+    fn parse_startup_message_handles_quoting() {
+        let (kind, fields) =
+            parse_startup_message(r#"new: ID=foo NAME="Some \"App\"" DESKTOP=2"#).unwrap();
+
+        assert_eq!(kind, "new");
+        assert_eq!(fields["ID"], "foo");
+        assert_eq!(fields["NAME"], r#"Some "App""#);
+        assert_eq!(fields["DESKTOP"], "2");
+    }
+
     struct WmNameXConn {
         wm_name: bool,
         net_wm_name: bool,
@@ -307,6 +408,53 @@ mod tests {
         }
     }
 
+    struct MaximizedXConn {
+        states: Vec<String>,
+        positions: Cell<Vec<Region>>,
+    }
+    impl StubXConn for MaximizedXConn {
+        fn mock_window_wants_maximized(&self, _: WinId) -> (bool, bool) {
+            let has = |a: Atom| self.states.iter().any(|s| s == a.as_ref());
+            (
+                has(Atom::NetWmStateMaximizedVert),
+                has(Atom::NetWmStateMaximizedHorz),
+            )
+        }
+
+        fn mock_window_geometry(&self, _: WinId) -> Result<Region> {
+            Ok(Region::new(100, 100, 200, 100))
+        }
+
+        fn mock_position_window(&self, _: WinId, r: Region, _: u32, _: bool) {
+            let mut v = self.positions.take();
+            v.push(r);
+            self.positions.set(v);
+        }
+    }
+
+    test_cases! {
+        position_floating_client;
+        args: (states: &[Atom], expected: Region);
+
+        case: not_maximized => (&[], Region::new(101, 101, 198, 98));
+        case: vert => (&[Atom::NetWmStateMaximizedVert], Region::new(101, 0, 198, 798));
+        case: horz => (&[Atom::NetWmStateMaximizedHorz], Region::new(0, 101, 998, 98));
+        case: both => (
+            &[Atom::NetWmStateMaximizedVert, Atom::NetWmStateMaximizedHorz],
+            Region::new(0, 0, 998, 798)
+        );
+
+        body: {
+            let conn = MaximizedXConn {
+                states: states.iter().map(|a| a.as_ref().to_string()).collect(),
+                positions: Cell::new(Vec::new()),
+            };
+            position_floating_client(&conn, 42, Region::new(0, 0, 1000, 800), 1).unwrap();
+
+            assert_eq!(conn.positions.take(), vec![expected]);
+        }
+    }
+
     struct OutputsXConn(Vec<Screen>);
     impl StubXConn for OutputsXConn {
         fn mock_current_outputs(&self) -> Vec<Screen> {
@@ -9,6 +9,8 @@ pub struct Screen {
     pub wix: usize,
     true_region: Region,
     effective_region: Region,
+    #[cfg_attr(feature = "serde", serde(default))]
+    refresh_mhz: Option<u32>,
 }
 
 impl Screen {
@@ -18,9 +20,20 @@ impl Screen {
             true_region: region,
             effective_region: region,
             wix,
+            refresh_mhz: None,
         }
     }
 
+    /// The refresh rate of this screen in Hz, if known.
+    pub fn refresh_hz(&self) -> Option<f64> {
+        self.refresh_mhz.map(|mhz| mhz as f64 / 1000.0)
+    }
+
+    /// Set the refresh rate of this screen in mHz (thousandths of a Hz)
+    pub(crate) fn set_refresh_mhz(&mut self, mhz: Option<u32>) {
+        self.refresh_mhz = mhz;
+    }
+
     /// Cache the current effective region of this screen based on whether or not a bar is
     /// displayed and if that bar is positioned at the top or bottom of the screen.
     pub fn update_effective_region(&mut self, bar_height: u32, top_bar: bool) {
@@ -42,6 +55,29 @@ impl Screen {
         }
     }
 
+    /// The space reserved on each edge of this screen (for a bar or similar) as the difference
+    /// between the true and effective regions, in the same (left, right, top, bottom) order as
+    /// `_NET_WM_STRUT`.
+    ///
+    /// ```
+    /// # use penrose::core::{data_types::Region, screen::Screen};
+    /// let mut s = Screen::new(Region::new(0, 0, 1000, 800), 0);
+    /// s.update_effective_region(20, true);
+    ///
+    /// assert_eq!(s.reserved(), (0, 0, 20, 0));
+    /// ```
+    pub fn reserved(&self) -> (u32, u32, u32, u32) {
+        let (tx, ty, tw, th) = self.true_region.values();
+        let (ex, ey, ew, eh) = self.effective_region.values();
+
+        let left = ex.saturating_sub(tx);
+        let top = ey.saturating_sub(ty);
+        let right = (tx + tw).saturating_sub(ex + ew);
+        let bottom = (ty + th).saturating_sub(ey + eh);
+
+        (left, right, top, bottom)
+    }
+
     /// Determine whether or not an absolute coordinate Point (relative to the root window) is
     /// located on this screen.
     pub fn contains(&self, p: Point) -> bool {
@@ -268,6 +268,7 @@ impl Workspace {
                 .clients
                 .iter()
                 .map(|id| client_map.get(id).unwrap())
+                .filter(|c| !c.iconified)
                 .partition(|c| c.floating);
 
             debug!(
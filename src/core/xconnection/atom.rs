@@ -23,21 +23,42 @@ pub enum Atom {
     /// MANAGER
     #[strum(serialize = "MANAGER")]
     Manager,
+    /// RESOURCE_MANAGER
+    #[strum(serialize = "RESOURCE_MANAGER")]
+    ResourceManager,
     /// UTF8_STRING
     #[strum(serialize = "UTF8_STRING")]
     UTF8String,
     /// WM_CLASS
     #[strum(serialize = "WM_CLASS")]
     WmClass,
+    /// WM_CHANGE_STATE
+    #[strum(serialize = "WM_CHANGE_STATE")]
+    WmChangeState,
+    /// WM_CLIENT_LEADER
+    #[strum(serialize = "WM_CLIENT_LEADER")]
+    WmClientLeader,
+    /// WM_COMMAND
+    #[strum(serialize = "WM_COMMAND")]
+    WmCommand,
     /// WM_DELETE_WINDOW
     #[strum(serialize = "WM_DELETE_WINDOW")]
     WmDeleteWindow,
+    /// WM_HINTS
+    #[strum(serialize = "WM_HINTS")]
+    WmHints,
+    /// WM_NORMAL_HINTS
+    #[strum(serialize = "WM_NORMAL_HINTS")]
+    WmNormalHints,
     /// WM_PROTOCOLS
     #[strum(serialize = "WM_PROTOCOLS")]
     WmProtocols,
     /// WM_STATE
     #[strum(serialize = "WM_STATE")]
     WmState,
+    /// WM_ICON_NAME
+    #[strum(serialize = "WM_ICON_NAME")]
+    WmIconName,
     /// WM_NAME
     #[strum(serialize = "WM_NAME")]
     WmName,
@@ -47,6 +68,9 @@ pub enum Atom {
     /// WM_TAKE_FOCUS
     #[strum(serialize = "WM_TAKE_FOCUS")]
     WmTakeFocus,
+    /// _GTK_FRAME_EXTENTS
+    #[strum(serialize = "_GTK_FRAME_EXTENTS")]
+    GtkFrameExtents,
     /// _NET_ACTIVE_WINDOW
     #[strum(serialize = "_NET_ACTIVE_WINDOW")]
     NetActiveWindow,
@@ -62,9 +86,24 @@ pub enum Atom {
     /// _NET_DESKTOP_NAMES
     #[strum(serialize = "_NET_DESKTOP_NAMES")]
     NetDesktopNames,
+    /// _NET_FRAME_EXTENTS
+    #[strum(serialize = "_NET_FRAME_EXTENTS")]
+    NetFrameExtents,
     /// _NET_NUMBER_OF_DESKTOPS
     #[strum(serialize = "_NET_NUMBER_OF_DESKTOPS")]
     NetNumberOfDesktops,
+    /// _NET_REQUEST_FRAME_EXTENTS
+    #[strum(serialize = "_NET_REQUEST_FRAME_EXTENTS")]
+    NetRequestFrameExtents,
+    /// _NET_STARTUP_ID
+    #[strum(serialize = "_NET_STARTUP_ID")]
+    NetStartupId,
+    /// _NET_STARTUP_INFO
+    #[strum(serialize = "_NET_STARTUP_INFO")]
+    NetStartupInfo,
+    /// _NET_STARTUP_INFO_BEGIN
+    #[strum(serialize = "_NET_STARTUP_INFO_BEGIN")]
+    NetStartupInfoBegin,
     /// _NET_SUPPORTED
     #[strum(serialize = "_NET_SUPPORTED")]
     NetSupported,
@@ -83,18 +122,60 @@ pub enum Atom {
     /// _NET_SYSTEM_TRAY_S0
     #[strum(serialize = "_NET_SYSTEM_TRAY_S0")]
     NetSystemTrayS0,
+    /// _NET_VIRTUAL_ROOTS
+    #[strum(serialize = "_NET_VIRTUAL_ROOTS")]
+    NetVirtualRoots,
+    /// _NET_WM_BYPASS_COMPOSITOR
+    #[strum(serialize = "_NET_WM_BYPASS_COMPOSITOR")]
+    NetWmBypassCompositor,
+    /// _NET_WM_CM_S0
+    #[strum(serialize = "_NET_WM_CM_S0")]
+    NetWmCmS0,
     /// _NET_WM_DESKTOP
     #[strum(serialize = "_NET_WM_DESKTOP")]
     NetWmDesktop,
+    /// _NET_WM_HANDLED_ICONS
+    #[strum(serialize = "_NET_WM_HANDLED_ICONS")]
+    NetWmHandledIcons,
+    /// _NET_WM_ICON_NAME
+    #[strum(serialize = "_NET_WM_ICON_NAME")]
+    NetWmIconName,
     /// _NET_WM_NAME
     #[strum(serialize = "_NET_WM_NAME")]
     NetWmName,
+    /// _NET_WM_OPAQUE_REGION
+    #[strum(serialize = "_NET_WM_OPAQUE_REGION")]
+    NetWmOpaqueRegion,
+    /// _NET_WM_PID
+    #[strum(serialize = "_NET_WM_PID")]
+    NetWmPid,
     /// _NET_WM_STATE
     #[strum(serialize = "_NET_WM_STATE")]
     NetWmState,
+    /// _NET_WM_STATE_ABOVE
+    #[strum(serialize = "_NET_WM_STATE_ABOVE")]
+    NetWmStateAbove,
     /// _NET_WM_STATE_FULLSCREEN
     #[strum(serialize = "_NET_WM_STATE_FULLSCREEN")]
     NetWmStateFullscreen,
+    /// _NET_WM_STATE_HIDDEN
+    #[strum(serialize = "_NET_WM_STATE_HIDDEN")]
+    NetWmStateHidden,
+    /// _NET_WM_STATE_MAXIMIZED_HORZ
+    #[strum(serialize = "_NET_WM_STATE_MAXIMIZED_HORZ")]
+    NetWmStateMaximizedHorz,
+    /// _NET_WM_STATE_MAXIMIZED_VERT
+    #[strum(serialize = "_NET_WM_STATE_MAXIMIZED_VERT")]
+    NetWmStateMaximizedVert,
+    /// _NET_WM_STATE_MODAL
+    #[strum(serialize = "_NET_WM_STATE_MODAL")]
+    NetWmStateModal,
+    /// _NET_WM_STATE_SKIP_PAGER
+    #[strum(serialize = "_NET_WM_STATE_SKIP_PAGER")]
+    NetWmStateSkipPager,
+    /// _NET_WM_STATE_SKIP_TASKBAR
+    #[strum(serialize = "_NET_WM_STATE_SKIP_TASKBAR")]
+    NetWmStateSkipTaskbar,
     /// _NET_WM_WINDOW_TYPE
     #[strum(serialize = "_NET_WM_WINDOW_TYPE")]
     NetWmWindowType,
@@ -162,6 +243,10 @@ pub const AUTO_FLOAT_WINDOW_TYPES: &[Atom] = &[
     Atom::NetWindowTypeUtility,
 ];
 
+/// Windows with a type in this array will not be given a border
+pub const NO_BORDER_WINDOW_TYPES: &[Atom] =
+    &[Atom::NetWindowTypeNotification, Atom::NetWindowTypeSplash];
+
 /// Windows with a type in this array will not be managed by penrose
 pub const UNMANAGED_WINDOW_TYPES: &[Atom] = &[
     Atom::NetWindowTypeDock,
@@ -177,14 +262,18 @@ pub const EWMH_SUPPORTED_ATOMS: &[Atom] = &[
     Atom::NetClientListStacking,
     Atom::NetCurrentDesktop,
     Atom::NetDesktopNames,
+    Atom::NetFrameExtents,
     Atom::NetNumberOfDesktops,
+    Atom::NetRequestFrameExtents,
     Atom::NetSupported,
     Atom::NetSupportingWmCheck,
     // Atom::NetSystemTrayS0,
     // Atom::NetSystemTrayOpcode,
     // Atom::NetSystemTrayOrientationHorz,
+    Atom::NetWmHandledIcons,
     Atom::NetWmName,
     Atom::NetWmState,
     Atom::NetWmStateFullscreen,
+    Atom::NetWmStateHidden,
     Atom::NetWmWindowType,
 ];
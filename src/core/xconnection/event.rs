@@ -1,7 +1,7 @@
 //! Data types for working with X events
 use crate::core::{
     bindings::{KeyCode, MouseEvent},
-    data_types::{Point, Region, WinId},
+    data_types::{CirculatePlace, OutputConnection, Point, Region, WinId},
 };
 
 /// Wrapper around the low level X event types that correspond to request / response data when
@@ -16,7 +16,12 @@ pub enum XEvent {
     MouseEvent(MouseEvent),
 
     /// A grabbed key combination has been entered by the user
-    KeyPress(KeyCode),
+    KeyPress {
+        /// The key combination that was pressed
+        code: KeyCode,
+        /// The root window that the grab fired on
+        root: WinId,
+    },
 
     /// A client window is requesting to be positioned and rendered on the screen
     MapRequest {
@@ -26,6 +31,20 @@ pub enum XEvent {
         ignore: bool,
     },
 
+    /// A window has been mapped to the screen
+    MapNotify {
+        /// The ID of the window that was mapped
+        id: WinId,
+        /// Whether or not this window has override-redirect set (such as a screen locker)
+        override_redirect: bool,
+    },
+
+    /// A window has been unmapped from the screen
+    UnmapNotify {
+        /// The ID of the window that was unmapped
+        id: WinId,
+    },
+
     /// The mouse pointer has entered a new client window
     Enter {
         /// The ID of the window that was entered
@@ -34,6 +53,8 @@ pub enum XEvent {
         rpt: Point,
         /// Coordinate of the event relative to top-left of the window itself
         wpt: Point,
+        /// The X server timestamp of the event in milliseconds
+        time: u32,
     },
 
     /// The mouse pointer has left the current client window
@@ -44,6 +65,8 @@ pub enum XEvent {
         rpt: Point,
         /// Coordinate of the event relative to top-left of the window itself
         wpt: Point,
+        /// The X server timestamp of the event in milliseconds
+        time: u32,
     },
 
     /// A client window has been closed
@@ -52,12 +75,51 @@ pub enum XEvent {
         id: WinId,
     },
 
+    /// The window used for `_NET_SUPPORTING_WM_CHECK` has been destroyed by another program
+    CheckWindowLost,
+
     /// Focus has moved to a different screen
     ScreenChange,
 
+    /// We have lost ownership of a selection (such as `_NET_SYSTEM_TRAY_S0`) to another client
+    SelectionClear {
+        /// The interned atom of the selection that was lost
+        selection: u32,
+    },
+
     /// A randr action has occured (new outputs, resolution change etc)
     RandrNotify,
 
+    /// A physical output has been connected, disconnected or had its mode changed
+    OutputChange {
+        /// The randr ID of the output that changed
+        output: u32,
+        /// The new connection state of the output
+        connection: OutputConnection,
+        /// The randr ID of the new mode for the output (0 if disabled)
+        mode: u32,
+    },
+
+    /// The active keyboard layout group has changed
+    KeyboardLayoutChange {
+        /// The index of the newly active layout group
+        group: u8,
+    },
+
+    /// The state of the keyboard LEDs (Caps Lock, Num Lock etc) has changed
+    KeyboardStateChange {
+        /// The new LED mask
+        leds: u32,
+    },
+
+    /// A window has been moved to the top or bottom of the stack by a client
+    CirculateNotify {
+        /// The ID of the window that was circulated
+        id: WinId,
+        /// Where in the stack the window now is
+        place: CirculatePlace,
+    },
+
     /// Client config has changed in some way
     ConfigureNotify {
         /// The ID of the window that had a property changed
@@ -92,7 +154,10 @@ pub enum XEvent {
     PropertyNotify {
         /// The ID of the window that had a property changed
         id: WinId,
-        /// The property that changed
+        /// The name of the property that changed.
+        ///
+        /// Changes to `WM_NORMAL_HINTS` (size hints such as resize increments) should trigger a
+        /// re-layout of the window's workspace so that the new hints are respected.
         atom: String,
         /// Is this window the root window?
         is_root: bool,
@@ -24,10 +24,11 @@ pub mod event;
 pub mod property;
 
 pub use atom::{
-    Atom, AtomIter, AUTO_FLOAT_WINDOW_TYPES, EWMH_SUPPORTED_ATOMS, UNMANAGED_WINDOW_TYPES,
+    Atom, AtomIter, AUTO_FLOAT_WINDOW_TYPES, EWMH_SUPPORTED_ATOMS, NO_BORDER_WINDOW_TYPES,
+    UNMANAGED_WINDOW_TYPES,
 };
 pub use event::XEvent;
-pub use property::{Prop, WmHints, WmNormalHints, WmNormalHintsFlags};
+pub use property::{Prop, WindowState, WmHints, WmNormalHints, WmNormalHintsFlags};
 
 /// A handle on a running X11 connection that we can use for issuing X requests.
 ///
@@ -54,6 +55,12 @@ pub trait XConn {
     /// Flush pending actions to the X event loop
     fn flush(&self) -> bool;
 
+    /// Flush pending actions and block until the X server has processed them.
+    ///
+    /// Unlike [flush][XConn::flush], this acts as a barrier: once it returns, all prior requests
+    /// have taken effect.
+    fn sync(&self);
+
     /// Wait for the next event from the X server and return it as an [XEvent]
     fn wait_for_event(&self) -> Result<XEvent>;
 
@@ -66,6 +73,12 @@ pub trait XConn {
     /// Reposition the window identified by 'id' to the specifed region
     fn position_window(&self, id: WinId, r: Region, border: u32, stack_above: bool);
 
+    /// Discard any further configure requests for the window identified by 'id' that are
+    /// already pending, returning the region requested by the most recent one (if any).
+    ///
+    /// All other pending events must be preserved.
+    fn latest_configure_request(&self, id: WinId) -> Option<Region>;
+
     /// Raise the window to the top of the stack so it renders above peers
     fn raise_window(&self, id: WinId);
 
@@ -78,11 +91,37 @@ pub trait XConn {
     /// Unmap a window from the display. Called each time an unmap_notify event is received
     fn unmap_window(&self, id: WinId);
 
+    /// Map each of the given windows, sending all of the requests before flushing so that they
+    /// are displayed together (e.g. when switching workspaces)
+    fn map_windows(&self, ids: &[WinId]);
+
+    /// Unmap each of the given windows, sending all of the requests before flushing
+    fn unmap_windows(&self, ids: &[WinId]);
+
+    /// Iconify (minimize) a window: setting its ICCCM WM_STATE to Iconic, adding
+    /// _NET_WM_STATE_HIDDEN and unmapping it.
+    ///
+    /// penrose never draws icons for iconified windows so there is nothing further to do for
+    /// pagers that set _NET_WM_HANDLED_ICONS.
+    fn iconify(&self, id: WinId);
+
+    /// Restore a previously iconified window: setting its ICCCM WM_STATE to Normal and removing
+    /// _NET_WM_STATE_HIDDEN.
+    ///
+    /// The window is not mapped: that is left to the [WindowManager][1] so that windows on
+    /// workspaces that are not currently visible stay hidden.
+    ///
+    /// [1]: crate::core::manager::WindowManager
+    fn deiconify(&self, id: WinId);
+
     /// Send an X event to the target window
     fn send_client_event(&self, id: WinId, atom_name: &str) -> Result<()>;
 
     /// Return the client ID of the [crate::core::client::Client] that currently holds X focus
-    fn focused_client(&self) -> WinId;
+    ///
+    /// Returns None if no client window holds focus (e.g. focus is set to None, PointerRoot or
+    /// the root window itself).
+    fn focused_client(&self) -> Option<WinId>;
 
     /// Mark the given [crate::core::client::Client] as having focus
     fn focus_client(&self, id: WinId);
@@ -102,6 +141,12 @@ pub trait XConn {
     /// Set required EWMH properties to ensure compatability with external programs
     fn set_wm_properties(&self, workspaces: &[&str]);
 
+    /// Create a new `_NET_SUPPORTING_WM_CHECK` window to replace one that has been destroyed.
+    ///
+    /// [set_wm_properties][XConn::set_wm_properties] should be called afterwards to point the
+    /// root window at the new check window.
+    fn recreate_check_window(&self) -> Result<()>;
+
     /// Update the root window properties with the current desktop details
     fn update_desktops(&self, workspaces: &[&str]);
 
@@ -121,11 +166,26 @@ pub trait XConn {
     fn toggle_client_fullscreen(&self, id: WinId, client_is_fullscreen: bool);
 
     /// Determine whether the target window should be tiled or allowed to float
+    ///
+    /// A window that explicitly sets `_NET_WM_WINDOW_TYPE_NORMAL` as its only window type should
+    /// always be tiled, taking precedence over any matching `floating_classes`. Otherwise, windows
+    /// are floated if their `WM_CLASS` is in `floating_classes` or if they have an
+    /// [auto float window type][1].
+    ///
+    /// [1]: crate::core::xconnection::AUTO_FLOAT_WINDOW_TYPES
     fn window_should_float(&self, id: WinId, floating_classes: &[&str]) -> bool;
 
     /// Check to see if this window is one that we should be handling or not
     fn is_managed_window(&self, id: WinId) -> bool;
 
+    /// Whether the target window is an override-redirect window that covers at least one full
+    /// output, such as a screen locker.
+    fn is_fullscreen_override(&self, id: WinId) -> bool;
+
+    /// Whether the target window has requested to be maximized as a (vertical, horizontal) pair
+    /// via `_NET_WM_STATE_MAXIMIZED_VERT` and `_NET_WM_STATE_MAXIMIZED_HORZ`.
+    fn window_wants_maximized(&self, id: WinId) -> (bool, bool);
+
     /// Return the current (x, y, w, h) dimensions of the requested window
     fn window_geometry(&self, id: WinId) -> Result<Region>;
 
@@ -136,6 +196,11 @@ pub trait XConn {
     /// Run on startup/restart to determine already running windows that we need to track
     fn query_for_active_windows(&self) -> Vec<WinId>;
 
+    /// The top level windows that are currently mapped (viewable), regardless of whether or not
+    /// they are managed. Unlike [query_for_active_windows][XConn::query_for_active_windows] this
+    /// excludes unmapped and withdrawn windows.
+    fn mapped_windows(&self) -> Vec<WinId>;
+
     /// Query a property for a window by window ID and name.
     ///
     /// Can fail if the property name is invalid or we get a malformed response from xcb.
@@ -150,8 +215,52 @@ pub trait XConn {
     /// Intern an X atom by name and return the corresponding ID
     fn intern_atom(&self, atom: &str) -> Result<u32>;
 
+    /// Set an arbitrary CARDINAL property on a window by name.
+    ///
+    /// This allows for setting properties that penrose itself does not know about, such as
+    /// hints read by a compositor.
+    fn set_cardinal_prop(&self, id: WinId, name: &str, values: &[u32]) -> Result<()>;
+
+    /// Set an arbitrary UTF8_STRING property on a window by name.
+    ///
+    /// The value can be read back using [get_prop][XConn::get_prop].
+    fn set_string_prop(&self, id: WinId, name: &str, value: &str) -> Result<()>;
+
     /// Perform any state cleanup required prior to shutting down the window manager
     fn cleanup(&self);
+
+    /// An iterator over the [XEvent]s received from the X server.
+    ///
+    /// Each call to `next` blocks on [wait_for_event][XConn::wait_for_event] and the iterator
+    /// ends the first time that an error is returned.
+    fn events(&self) -> XEvents<'_, Self>
+    where
+        Self: Sized,
+    {
+        XEvents { conn: self }
+    }
+}
+
+/// An [Iterator] of [XEvent]s pulled from an [XConn].
+///
+/// See [XConn::events] for details.
+#[derive(Debug)]
+pub struct XEvents<'a, X: XConn> {
+    conn: &'a X,
+}
+
+impl<'a, X: XConn> Iterator for XEvents<'a, X> {
+    type Item = XEvent;
+
+    fn next(&mut self) -> Option<XEvent> {
+        match self.conn.wait_for_event() {
+            Ok(event) => Some(event),
+            Err(e) => {
+                debug!("event stream closed: {}", e);
+                None
+            }
+        }
+    }
 }
 
 /// A really simple stub implementation of [XConn] to simplify setting up test cases.
@@ -180,6 +289,9 @@ pub trait StubXConn {
         true
     }
 
+    /// Mocked version of sync
+    fn mock_sync(&self) {}
+
     /// Mocked version of wait_for_event
     fn mock_wait_for_event(&self) -> Result<XEvent> {
         Err(PenroseError::Raw("mock impl".into()))
@@ -201,8 +313,8 @@ pub trait StubXConn {
     }
 
     /// Mocked version of focused_client
-    fn mock_focused_client(&self) -> WinId {
-        0
+    fn mock_focused_client(&self) -> Option<WinId> {
+        Some(0)
     }
 
     /// Mocked version of window_should_float
@@ -215,6 +327,16 @@ pub trait StubXConn {
         true
     }
 
+    /// Mocked version of is_fullscreen_override
+    fn mock_is_fullscreen_override(&self, _: WinId) -> bool {
+        false
+    }
+
+    /// Mocked version of window_wants_maximized
+    fn mock_window_wants_maximized(&self, _: WinId) -> (bool, bool) {
+        (false, false)
+    }
+
     /// Mocked version of window_geometry
     fn mock_window_geometry(&self, _: WinId) -> Result<Region> {
         Ok(Region::new(0, 0, 0, 0))
@@ -225,6 +347,11 @@ pub trait StubXConn {
         Vec::new()
     }
 
+    /// Mocked version of mapped_windows
+    fn mock_mapped_windows(&self) -> Vec<WinId> {
+        Vec::new()
+    }
+
     /// Mocked version of get_prop
     fn mock_get_prop(&self, _: WinId, prop: &str) -> Result<Prop> {
         if prop == Atom::WmName.as_ref() || prop == Atom::NetWmName.as_ref() {
@@ -244,12 +371,26 @@ pub trait StubXConn {
         Ok(0)
     }
 
+    /// Mocked version of set_cardinal_prop
+    fn mock_set_cardinal_prop(&self, _: WinId, _: &str, _: &[u32]) -> Result<()> {
+        Ok(())
+    }
+
+    /// Mocked version of set_string_prop
+    fn mock_set_string_prop(&self, _: WinId, _: &str, _: &str) -> Result<()> {
+        Ok(())
+    }
+
     /// Mocked version of warp_cursor
     fn mock_warp_cursor(&self, _: Option<WinId>, _: &Screen) {}
     /// Mocked version of focus_client
     fn mock_focus_client(&self, _: WinId) {}
     /// Mocked version of position_window
     fn mock_position_window(&self, _: WinId, _: Region, _: u32, _: bool) {}
+    /// Mocked version of latest_configure_request
+    fn mock_latest_configure_request(&self, _: WinId) -> Option<Region> {
+        None
+    }
     /// Mocked version of raise_window
     fn mock_raise_window(&self, _: WinId) {}
     /// Mocked version of mark_new_window
@@ -258,6 +399,18 @@ pub trait StubXConn {
     fn mock_map_window(&self, _: WinId) {}
     /// Mocked version of unmap_window
     fn mock_unmap_window(&self, _: WinId) {}
+    /// Mocked version of map_windows
+    fn mock_map_windows(&self, ids: &[WinId]) {
+        ids.iter().for_each(|&id| self.mock_map_window(id))
+    }
+    /// Mocked version of unmap_windows
+    fn mock_unmap_windows(&self, ids: &[WinId]) {
+        ids.iter().for_each(|&id| self.mock_unmap_window(id))
+    }
+    /// Mocked version of iconify
+    fn mock_iconify(&self, _: WinId) {}
+    /// Mocked version of deiconify
+    fn mock_deiconify(&self, _: WinId) {}
     /// Mocked version of set_client_border_color
     fn mock_set_client_border_color(&self, _: WinId, _: Color) {}
     /// Mocked version of grab_keys
@@ -268,6 +421,10 @@ pub trait StubXConn {
     }
     /// Mocked version of set_wm_properties
     fn mock_set_wm_properties(&self, _: &[&str]) {}
+    /// Mocked version of recreate_check_window
+    fn mock_recreate_check_window(&self) -> Result<()> {
+        Ok(())
+    }
     /// Mocked version of update_desktops
     fn mock_update_desktops(&self, _: &[&str]) {}
     /// Mocked version of update_known_clients
@@ -301,6 +458,10 @@ where
         self.mock_flush()
     }
 
+    fn sync(&self) {
+        self.mock_sync()
+    }
+
     fn wait_for_event(&self) -> Result<XEvent> {
         self.mock_wait_for_event()
     }
@@ -317,6 +478,10 @@ where
         self.mock_position_window(id, r, border, stack_above)
     }
 
+    fn latest_configure_request(&self, id: WinId) -> Option<Region> {
+        self.mock_latest_configure_request(id)
+    }
+
     fn raise_window(&self, id: WinId) {
         self.mock_raise_window(id)
     }
@@ -333,11 +498,27 @@ where
         self.mock_unmap_window(id)
     }
 
+    fn map_windows(&self, ids: &[WinId]) {
+        self.mock_map_windows(ids)
+    }
+
+    fn unmap_windows(&self, ids: &[WinId]) {
+        self.mock_unmap_windows(ids)
+    }
+
+    fn iconify(&self, id: WinId) {
+        self.mock_iconify(id)
+    }
+
+    fn deiconify(&self, id: WinId) {
+        self.mock_deiconify(id)
+    }
+
     fn send_client_event(&self, id: WinId, atom_name: &str) -> Result<()> {
         self.mock_send_client_event(id, atom_name)
     }
 
-    fn focused_client(&self) -> WinId {
+    fn focused_client(&self) -> Option<WinId> {
         self.mock_focused_client()
     }
 
@@ -357,6 +538,10 @@ where
         self.mock_set_wm_properties(workspaces)
     }
 
+    fn recreate_check_window(&self) -> Result<()> {
+        self.mock_recreate_check_window()
+    }
+
     fn update_desktops(&self, workspaces: &[&str]) {
         self.mock_update_desktops(workspaces)
     }
@@ -389,6 +574,14 @@ where
         self.mock_is_managed_window(id)
     }
 
+    fn is_fullscreen_override(&self, id: WinId) -> bool {
+        self.mock_is_fullscreen_override(id)
+    }
+
+    fn window_wants_maximized(&self, id: WinId) -> (bool, bool) {
+        self.mock_window_wants_maximized(id)
+    }
+
     fn window_geometry(&self, id: WinId) -> Result<Region> {
         self.mock_window_geometry(id)
     }
@@ -401,6 +594,10 @@ where
         self.mock_query_for_active_windows()
     }
 
+    fn mapped_windows(&self) -> Vec<WinId> {
+        self.mock_mapped_windows()
+    }
+
     fn get_prop(&self, id: WinId, name: &str) -> Result<Prop> {
         self.mock_get_prop(id, name)
     }
@@ -413,6 +610,14 @@ where
         self.mock_intern_atom(atom)
     }
 
+    fn set_cardinal_prop(&self, id: WinId, name: &str, values: &[u32]) -> Result<()> {
+        self.mock_set_cardinal_prop(id, name, values)
+    }
+
+    fn set_string_prop(&self, id: WinId, name: &str, value: &str) -> Result<()> {
+        self.mock_set_string_prop(id, name, value)
+    }
+
     fn cleanup(&self) {
         self.mock_cleanup()
     }
@@ -471,8 +676,8 @@ impl StubXConn for MockXConn {
         self.screens.clone()
     }
 
-    fn mock_focused_client(&self) -> WinId {
-        self.focused.get()
+    fn mock_focused_client(&self) -> Option<WinId> {
+        Some(self.focused.get())
     }
 
     fn mock_focus_client(&self, id: WinId) {
@@ -483,3 +688,16 @@ impl StubXConn for MockXConn {
         !self.unmanaged_ids.contains(&id)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_terminates_when_mock_events_are_exhausted() {
+        let events = vec![XEvent::ScreenChange, XEvent::RandrNotify];
+        let conn = MockXConn::new(vec![], events, vec![]);
+
+        assert_eq!(conn.events().count(), 2);
+    }
+}
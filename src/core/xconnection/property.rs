@@ -80,7 +80,7 @@ bitflags! {
 
 /// The display states that a window can be in.
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-#[derive(Debug, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum WindowState {
     /// Window is not visible
     Withdrawn,
@@ -132,6 +132,13 @@ impl WmHints {
         }
     }
 
+    /// Whether or not the client relies on the window manager to give it input focus.
+    ///
+    /// This is true if the input hint is set to true or if it has not been set at all.
+    pub fn accepts_input(&self) -> bool {
+        self.accepts_input
+    }
+
     /// Try to construct a [WmHints] instance from raw bytes.
     ///
     /// This method expects a slice of 9 u32s corresponding to the C struct layout shown below.
@@ -190,9 +197,10 @@ impl WmHints {
 /// See the ICCCM [spec][1] for further details or the [Xlib manual][2] for more details of the
 /// data fromat but note that Penrose does not honour the following hints:
 ///   - gravity
-///   - increment
 ///   - aspect ratio
 ///
+/// Resize increments are only applied when clamping interactive resizes via [WmNormalHints::clamp].
+///
 /// [1]: https://www.x.org/releases/X11R7.6/doc/xorg-docs/specs/ICCCM/icccm.html#wm_normal_hints_property
 /// [2]: https://tronche.com/gui/x/xlib/ICC/client-to-window-manager/wm-normal-hints.html
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -203,6 +211,8 @@ pub struct WmNormalHints {
     min: Option<Region>,
     max: Option<Region>,
     user_specified: Option<Region>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    resize_inc: Option<(u32, u32)>,
 }
 
 impl WmNormalHints {
@@ -220,9 +230,64 @@ impl WmNormalHints {
             min,
             max,
             user_specified,
+            resize_inc: None,
         }
     }
 
+    /// The base size requested by the client (if set)
+    pub fn base(&self) -> Option<Region> {
+        self.base
+    }
+
+    /// The minimum size requested by the client (if set)
+    pub fn min(&self) -> Option<Region> {
+        self.min
+    }
+
+    /// The maximum size requested by the client (if set)
+    pub fn max(&self) -> Option<Region> {
+        self.max
+    }
+
+    /// The (width, height) resize increments requested by the client (if set)
+    pub fn resize_inc(&self) -> Option<(u32, u32)> {
+        self.resize_inc
+    }
+
+    /// Apply the min, max and increment constraints of these hints to a proposed geometry.
+    ///
+    /// Increments are applied relative to the base size (falling back to the min size as per
+    /// the ICCCM) before the result is clamped to the min and max sizes. The position of the
+    /// proposed [Region] is left unchanged.
+    pub fn clamp(&self, proposed: Region) -> Region {
+        let (x, y, mut w, mut h) = proposed.values();
+
+        if let Some((w_inc, h_inc)) = self.resize_inc {
+            let (base_w, base_h) = match self.base.or(self.min) {
+                Some(r) => (r.w, r.h),
+                None => (0, 0),
+            };
+            if w_inc > 0 && w > base_w {
+                w = base_w + (w - base_w) / w_inc * w_inc;
+            }
+            if h_inc > 0 && h > base_h {
+                h = base_h + (h - base_h) / h_inc * h_inc;
+            }
+        }
+
+        if let Some(min) = self.min {
+            w = w.max(min.w);
+            h = h.max(min.h);
+        }
+
+        if let Some(max) = self.max {
+            w = w.min(max.w);
+            h = h.min(max.h);
+        }
+
+        Region::new(x, y, w, h)
+    }
+
     /// Try to construct a [WmNormalHints] instance from raw bytes.
     ///
     /// This method expects a slice of 18 u32s corresponding to the C struct layout shown below.
@@ -261,9 +326,10 @@ impl WmNormalHints {
 
         let (min_w, min_h) = (raw[5], raw[6]);
         let (max_w, max_h) = (raw[7], raw[8]);
+        let (inc_w, inc_h) = (raw[9], raw[10]);
         let (base_w, base_h) = (raw[15], raw[16]);
 
-        // ignoring increment, aspect ratio, gravity as they are not used in
+        // ignoring aspect ratio and gravity as they are not used in
         // the main WindowManager logic
 
         let if_set = |x, y, w, h| {
@@ -280,6 +346,52 @@ impl WmNormalHints {
             min: if_set(x, y, min_w, min_h),
             max: if_set(x, y, max_w, max_h),
             user_specified: if_set(x, y, user_w, user_h),
+            resize_inc: if inc_w > 0 && inc_h > 0 {
+                Some((inc_w, inc_h))
+            } else {
+                None
+            },
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hints(min: (u32, u32), max: (u32, u32), inc: (u32, u32), base: (u32, u32)) -> WmNormalHints {
+        let raw = [
+            0, 0, 0, 0, 0, min.0, min.1, max.0, max.1, inc.0, inc.1, 0, 0, 0, 0, base.0, base.1, 0,
+        ];
+
+        WmNormalHints::try_from_bytes(&raw).unwrap()
+    }
+
+    test_cases! {
+        normal_hints_clamp;
+        args: (
+            min: (u32, u32),
+            max: (u32, u32),
+            inc: (u32, u32),
+            base: (u32, u32),
+            proposed: (u32, u32),
+            expected: (u32, u32),
+        );
+
+        case: unconstrained => ((0, 0), (0, 0), (0, 0), (0, 0), (123, 456), (123, 456));
+        case: below_min => ((100, 200), (0, 0), (0, 0), (0, 0), (50, 50), (100, 200));
+        case: above_max => ((0, 0), (300, 400), (0, 0), (0, 0), (500, 500), (300, 400));
+        case: within_min_max => ((100, 100), (300, 300), (0, 0), (0, 0), (200, 250), (200, 250));
+        case: inc_from_zero => ((0, 0), (0, 0), (10, 20), (0, 0), (105, 119), (100, 100));
+        case: inc_from_base => ((0, 0), (0, 0), (10, 20), (5, 3), (107, 59), (105, 43));
+        case: inc_base_falls_back_to_min => ((4, 6), (0, 0), (10, 10), (0, 0), (37, 59), (34, 56));
+        case: inc_base_preferred_over_min => ((4, 6), (0, 0), (10, 10), (2, 2), (37, 59), (32, 52));
+        case: inc_then_max => ((0, 0), (95, 95), (10, 10), (0, 0), (120, 120), (95, 95));
+        case: inc_then_min => ((50, 50), (0, 0), (8, 8), (0, 0), (55, 10), (50, 50));
+
+        body: {
+            let r = hints(min, max, inc, base).clamp(Region::new(7, 9, proposed.0, proposed.1));
+            assert_eq!(r, Region::new(7, 9, expected.0, expected.1));
+        }
+    }
+}
@@ -2,17 +2,31 @@
 use crate::{
     core::{
         bindings::{KeyCode, KeyCodeMask, KeyCodeValue, MouseEvent, MouseState},
-        data_types::{Point, PropVal, Region, WinAttr, WinConfig, WinId, WinType},
+        data_types::{
+            CirculatePlace, GammaRamp, ImageData, ModeInfo, OutputConnection, Point, PointerState,
+            PropVal, Region, RelativePosition, Rotation, ServerInfo, WinAttr, WinConfig, WinId,
+            WinType,
+        },
         helpers::spawn_for_output,
         screen::Screen,
-        xconnection::{Atom, Prop, WmHints, WmNormalHints, XEvent, UNMANAGED_WINDOW_TYPES},
+        xconnection::{
+            Atom, Prop, WindowState, WmHints, WmNormalHints, XEvent, UNMANAGED_WINDOW_TYPES,
+        },
     },
     xcb::{Result, XcbError, XcbGenericEvent},
 };
 use strum::*;
 
-use std::{collections::HashMap, convert::TryFrom, fmt, str::FromStr};
+use std::{
+    cell::{Cell, RefCell},
+    collections::{HashMap, HashSet, VecDeque},
+    convert::TryFrom,
+    fmt,
+    str::FromStr,
+};
 
+#[cfg(feature = "dpms")]
+use crate::core::data_types::{DpmsInfo, DpmsMode};
 #[cfg(feature = "keysyms")]
 use crate::{core::bindings::KeyPress, draw::KeyPressParseAttempt};
 #[cfg(feature = "keysyms")]
@@ -23,6 +37,11 @@ pub type ReverseCodeMap = HashMap<(KeyCodeMask, KeyCodeValue), String>;
 
 const RANDR_MAJ: u32 = 1;
 const RANDR_MIN: u32 = 2;
+const BAR_FONT: &str = "fixed";
+#[cfg(feature = "xkb")]
+const XKB_MAJ: u16 = 1;
+#[cfg(feature = "xkb")]
+const XKB_MIN: u16 = 0;
 
 #[cfg(feature = "serde")]
 fn default_conn() -> xcb::Connection {
@@ -64,6 +83,60 @@ pub fn code_map_from_xmodmap() -> Result<ReverseCodeMap> {
         .collect::<HashMap<(u16, u8), String>>())
 }
 
+// The refresh rate of a RandR mode in mHz: dot_clock / (htotal * vtotal)
+fn mode_refresh_mhz(m: &xcb::randr::ModeInfo) -> Option<u32> {
+    let mut vtotal = m.vtotal() as u64;
+    if m.mode_flags() & xcb::randr::MODE_FLAG_DOUBLE_SCAN != 0 {
+        vtotal *= 2;
+    }
+    if m.mode_flags() & xcb::randr::MODE_FLAG_INTERLACE != 0 {
+        vtotal /= 2;
+    }
+
+    (m.dot_clock() as u64 * 1000)
+        .checked_div(m.htotal() as u64 * vtotal)
+        .map(|mhz| mhz as u32)
+}
+
+// Look up a single resource in the contents of a RESOURCE_MANAGER property. Lines are of the
+// form `key: value` with `!` marking comments and a trailing `\` continuing the line: later
+// definitions take precedence. Wildcard (`*`) bindings are matched literally, not expanded.
+fn parse_resource(db: &str, name: &str) -> Option<String> {
+    db.replace("\\\n", "")
+        .lines()
+        .filter(|line| !line.trim_start().starts_with('!'))
+        .filter_map(|line| {
+            let (key, val) = line.split_at(line.find(':')?);
+            Some((key.trim(), val[1..].trim()))
+        })
+        .filter(|(key, _)| *key == name)
+        .map(|(_, val)| val.to_string())
+        .next_back()
+}
+
+// Whether or not the given regions form a single connected block, with each one sharing at least
+// part of an edge with another (touching at a corner is not enough).
+fn regions_are_contiguous(regions: &[Region]) -> bool {
+    let touches = |a: &Region, b: &Region| {
+        let x_overlap = (a.x + a.w).min(b.x + b.w) as i64 - a.x.max(b.x) as i64;
+        let y_overlap = (a.y + a.h).min(b.y + b.h) as i64 - a.y.max(b.y) as i64;
+        x_overlap >= 0 && y_overlap >= 0 && (x_overlap > 0 || y_overlap > 0)
+    };
+
+    let mut seen = vec![false; regions.len()];
+    let mut pending = vec![0];
+    while let Some(i) = pending.pop() {
+        if i >= regions.len() || seen[i] {
+            continue;
+        }
+        seen[i] = true;
+        pending
+            .extend((0..regions.len()).filter(|&j| !seen[j] && touches(&regions[i], &regions[j])));
+    }
+
+    seen.into_iter().all(|s| s)
+}
+
 /// A connection to the X server using the XCB C API
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Api {
@@ -72,7 +145,18 @@ pub struct Api {
     root: WinId,
     check_win: WinId,
     randr_base: u8,
+    randr_version: (u32, u32),
+    #[cfg(feature = "xkb")]
+    xkb_base: u8,
     atoms: HashMap<Atom, u32>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    gcs: RefCell<HashSet<u32>>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    copy_gc: Cell<Option<u32>>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    fonts: RefCell<HashMap<String, u32>>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pending_events: RefCell<VecDeque<Result<XEvent>>>,
     #[cfg(feature = "keysyms")]
     code_map: ReverseCodeMap,
 }
@@ -82,6 +166,7 @@ impl fmt::Debug for Api {
         f.debug_struct("XCB Api")
             .field("root", &self.root)
             .field("randr_base", &self.randr_base)
+            .field("randr_version", &self.randr_version)
             .field("atoms", &self.atoms)
             .finish()
     }
@@ -113,7 +198,14 @@ impl Api {
             root: 0,
             check_win: 0,
             randr_base: 0,
+            randr_version: (0, 0),
+            #[cfg(feature = "xkb")]
+            xkb_base: 0,
             atoms: HashMap::new(),
+            gcs: RefCell::new(HashSet::new()),
+            copy_gc: Cell::new(None),
+            fonts: RefCell::new(HashMap::new()),
+            pending_events: RefCell::new(VecDeque::new()),
             #[cfg(feature = "keysyms")]
             code_map: code_map_from_xmodmap()?,
         };
@@ -138,6 +230,7 @@ impl Api {
         let cookie = xcb::randr::query_version(&self.conn, RANDR_MAJ, RANDR_MIN);
         let reply = cookie.get_reply()?;
         let (maj, min) = (reply.major_version(), reply.minor_version());
+        self.randr_version = (maj, min);
         if (maj, min) != (RANDR_MAJ, RANDR_MIN) {
             panic!(format!(
                 "penrose requires RandR version >= {}.{}: detected {}.{}\nplease update RandR to a newer version",
@@ -145,6 +238,9 @@ impl Api {
             ))
         }
 
+        #[cfg(feature = "xkb")]
+        self.init_xkb()?;
+
         self.check_win = self.conn.generate_id();
         xcb::create_window(
             &self.conn,
@@ -161,7 +257,50 @@ impl Api {
             &[],
         );
         self.conn.flush();
+        self.reintern_atoms()
+    }
+
+    // Enable the XKB extension and register for group state changes on the core keyboard
+    #[cfg(feature = "xkb")]
+    fn init_xkb(&mut self) -> Result<()> {
+        self.xkb_base = self
+            .conn
+            .get_extension_data(xcb::xkb::id())
+            .ok_or_else(|| XcbError::Raw("unable to fetch xkb extension data".into()))?
+            .first_event();
+
+        let reply = xcb::xkb::use_extension(&self.conn, XKB_MAJ, XKB_MIN).get_reply()?;
+        if !reply.supported() {
+            return Err(XcbError::Raw(format!(
+                "xkb version {}.{} is not supported by the X server",
+                XKB_MAJ, XKB_MIN
+            )));
+        }
+
+        let notify = (xcb::xkb::EVENT_TYPE_STATE_NOTIFY
+            | xcb::xkb::EVENT_TYPE_INDICATOR_STATE_NOTIFY) as u16;
+        xcb::xkb::select_events_checked(
+            &self.conn,
+            xcb::xkb::ID_USE_CORE_KBD as xcb::xkb::DeviceSpec,
+            notify,
+            0,
+            notify,
+            0,
+            0,
+            None,
+        )
+        .request_check()?;
+
+        Ok(())
+    }
 
+    /// Intern all known [Atom] values, replacing any existing cached values.
+    ///
+    /// Interned atom ids are only valid for the lifetime of the X server so this needs to be
+    /// called if the server has been restarted (e.g. a nested Xephyr server during development).
+    pub fn reintern_atoms(&mut self) -> Result<()> {
+        // Clear the cache first so that we don't simply get back the stale values
+        self.atoms.clear();
         self.atoms = Atom::iter()
             .map(|atom| {
                 let val = self.atom(atom.as_ref())?;
@@ -194,6 +333,233 @@ impl Api {
             .collect::<Result<Vec<String>>>()
     }
 
+    /// Check whether the target window is a modal dialog.
+    ///
+    /// Modal windows are transient for another window and have `_NET_WM_STATE_MODAL` set. While
+    /// they are open, focus should not be given to the window they are transient for.
+    pub fn window_is_modal(&self, id: WinId) -> bool {
+        if self.get_prop(id, Atom::WmTransientFor.as_ref()).is_err() {
+            return false;
+        }
+
+        self.window_has_state(id, Atom::NetWmStateModal)
+    }
+
+    /// Check whether the target window has requested not to be shown in taskbars via
+    /// `_NET_WM_STATE_SKIP_TASKBAR`.
+    pub fn window_skips_taskbar(&self, id: WinId) -> bool {
+        self.window_has_state(id, Atom::NetWmStateSkipTaskbar)
+    }
+
+    /// Check whether the target window has requested not to be shown in pagers via
+    /// `_NET_WM_STATE_SKIP_PAGER`.
+    pub fn window_skips_pager(&self, id: WinId) -> bool {
+        self.window_has_state(id, Atom::NetWmStateSkipPager)
+    }
+
+    /// Check whether the target window has asked compositors to unredirect it via
+    /// `_NET_WM_BYPASS_COMPOSITOR`.
+    ///
+    /// This is typically set by fullscreen games: a value of 1 requests that the window bypass
+    /// the compositor while 2 explicitly requests that it does not. Any other value (or the
+    /// property being absent) is treated as no preference.
+    pub fn window_bypasses_compositor(&self, id: WinId) -> bool {
+        matches!(
+            self.get_prop(id, Atom::NetWmBypassCompositor.as_ref()),
+            Ok(Prop::Cardinal(1))
+        )
+    }
+
+    /// Check whether the target window is able to take input focus at all.
+    ///
+    /// A window accepts focus if its WM_HINTS input hint is true (or not set) or if it supports
+    /// the WM_TAKE_FOCUS protocol. Windows that do neither (such as some splash screens) will
+    /// ignore attempts to focus them so focus should be given to another window instead.
+    pub fn window_accepts_focus(&self, id: WinId) -> bool {
+        let input_hint = match self.get_prop(id, Atom::WmHints.as_ref()) {
+            Ok(Prop::WmHints(hints)) => hints.accepts_input(),
+            _ => true,
+        };
+
+        input_hint
+            || match self.get_prop(id, Atom::WmProtocols.as_ref()) {
+                Ok(Prop::Atom(protocols)) => {
+                    protocols.iter().any(|p| p == Atom::WmTakeFocus.as_ref())
+                }
+                _ => false,
+            }
+    }
+
+    /// Whether the target window has requested to be maximized as a (vertical, horizontal) pair
+    /// via `_NET_WM_STATE_MAXIMIZED_VERT` and `_NET_WM_STATE_MAXIMIZED_HORZ`.
+    pub fn window_wants_maximized(&self, id: WinId) -> (bool, bool) {
+        match self.get_prop(id, Atom::NetWmState.as_ref()) {
+            Ok(Prop::Atom(states)) => {
+                let has = |a: Atom| states.iter().any(|s| s == a.as_ref());
+                (
+                    has(Atom::NetWmStateMaximizedVert),
+                    has(Atom::NetWmStateMaximizedHorz),
+                )
+            }
+            _ => (false, false),
+        }
+    }
+
+    /// Check whether the target window currently has the given `_NET_WM_STATE` set.
+    pub fn window_has_state(&self, id: WinId, state: Atom) -> bool {
+        match self.get_prop(id, Atom::NetWmState.as_ref()) {
+            Ok(Prop::Atom(states)) => states.iter().any(|s| s == state.as_ref()),
+            _ => false,
+        }
+    }
+
+    /// Add or remove a single `_NET_WM_STATE` value for the target window, leaving any other
+    /// states that are currently set untouched.
+    pub fn set_window_state(&self, id: WinId, state: Atom, enabled: bool) -> Result<()> {
+        let mut states = match self.get_prop(id, Atom::NetWmState.as_ref()) {
+            Ok(Prop::Atom(names)) => names
+                .iter()
+                .map(|name| self.atom(name))
+                .collect::<Result<Vec<u32>>>()?,
+            _ => vec![],
+        };
+
+        let atom = self.known_atom(state);
+        states.retain(|&a| a != atom);
+        if enabled {
+            states.push(atom);
+        }
+        self.replace_prop(id, Atom::NetWmState, PropVal::Atom(&states));
+
+        Ok(())
+    }
+
+    /// Look up a resource from the X resource database (the `RESOURCE_MANAGER` property on the
+    /// root window, as set by `xrdb`).
+    ///
+    /// `name` must match the key used in the database exactly (e.g. `*background` or
+    /// `URxvt.font`): wildcard matching of resource names is not performed.
+    pub fn resource(&self, name: &str) -> Option<String> {
+        let atom = self.known_atom(Atom::ResourceManager);
+        // The resource database can be large so request up to 1MB rather than the default
+        // used by get_prop
+        // xcb docs: https://www.mankier.com/3/xcb_get_property
+        let r = xcb::get_property(
+            &self.conn,
+            false,
+            self.root,
+            atom,
+            xcb::ATOM_STRING,
+            0,
+            1 << 18,
+        )
+        .get_reply()
+        .ok()?;
+
+        parse_resource(&String::from_utf8_lossy(r.value::<u8>()), name)
+    }
+
+    /// The startup notification ID of the target window (`_NET_STARTUP_ID`) if it has one.
+    ///
+    /// See the [startup notification spec][1] for details.
+    ///
+    /// [1]: https://specifications.freedesktop.org/startup-notification-spec/startup-notification-latest.txt
+    pub fn window_startup_id(&self, id: WinId) -> Option<String> {
+        match self.get_prop(id, Atom::NetStartupId.as_ref()) {
+            Ok(Prop::UTF8String(strs)) => strs.into_iter().next().filter(|s| !s.is_empty()),
+            _ => None,
+        }
+    }
+
+    /// The command line used to launch the target window as set in the ICCCM `WM_COMMAND`
+    /// property, split into its individual arguments.
+    ///
+    /// This is used by X session management to restart applications and is only set by some
+    /// (mostly older) clients.
+    pub fn window_command(&self, id: WinId) -> Option<Vec<String>> {
+        match self.get_prop(id, Atom::WmCommand.as_ref()) {
+            Ok(Prop::UTF8String(args)) if args.iter().any(|a| !a.is_empty()) => Some(args),
+            _ => None,
+        }
+    }
+
+    /// The name that should be shown for the target window when it is iconified.
+    ///
+    /// `_NET_WM_ICON_NAME` is preferred, falling back to the ICCCM `WM_ICON_NAME` and then an
+    /// empty string if neither is set.
+    pub fn window_icon_name(&self, id: WinId) -> String {
+        [Atom::NetWmIconName, Atom::WmIconName]
+            .iter()
+            .find_map(|a| match self.get_prop(id, a.as_ref()) {
+                Ok(Prop::UTF8String(strs)) => strs.into_iter().next().filter(|s| !s.is_empty()),
+                _ => None,
+            })
+            .unwrap_or_default()
+    }
+
+    /// Best effort check for whether two windows belong to the same client application.
+    ///
+    /// The `WM_CLIENT_LEADER` of each window is compared if both windows have one set, falling
+    /// back to `_NET_WM_PID` and then finally `WM_CLASS`.
+    pub fn same_client(&self, a: WinId, b: WinId) -> bool {
+        if a == b {
+            return true;
+        }
+
+        let props = |name: Atom| {
+            (
+                self.get_prop(a, name.as_ref()).ok(),
+                self.get_prop(b, name.as_ref()).ok(),
+            )
+        };
+
+        if let (Some(Prop::Window(l1)), Some(Prop::Window(l2))) = props(Atom::WmClientLeader) {
+            return l1 == l2;
+        }
+        if let (Some(Prop::Cardinal(p1)), Some(Prop::Cardinal(p2))) = props(Atom::NetWmPid) {
+            return p1 == p2;
+        }
+        match props(Atom::WmClass) {
+            (Some(Prop::UTF8String(c1)), Some(Prop::UTF8String(c2))) => c1.last() == c2.last(),
+            _ => false,
+        }
+    }
+
+    /// Whether or not the target window should be included when cycling focus between windows
+    /// (e.g. for an Alt-Tab style binding).
+    ///
+    /// Modal dialogs are always included so that they can not be lost behind the window they
+    /// belong to. Otherwise, docks, splash screens, notifications and windows that have asked
+    /// to be skipped by taskbars are excluded.
+    pub fn window_in_cycle(&self, id: WinId) -> bool {
+        if self.window_is_modal(id) {
+            return true;
+        }
+        if self.window_skips_taskbar(id) {
+            return false;
+        }
+
+        let excluded = [
+            Atom::NetWindowTypeDock,
+            Atom::NetWindowTypeNotification,
+            Atom::NetWindowTypeSplash,
+        ];
+        match self.get_prop(id, Atom::NetWmWindowType.as_ref()) {
+            Ok(Prop::Atom(types)) => !types
+                .iter()
+                .any(|t| excluded.iter().any(|a| a.as_ref() == t)),
+            _ => true,
+        }
+    }
+
+    /// Check whether the target window has override-redirect set.
+    pub fn window_is_override_redirect(&self, id: WinId) -> bool {
+        xcb::get_window_attributes(&self.conn, id)
+            .get_reply()
+            .map(|r| r.override_redirect())
+            .unwrap_or(false)
+    }
+
     /// Check to see if a window should be managed or not
     pub fn window_is_managed(&self, id: WinId) -> bool {
         if let Ok(_) = self.get_prop(id, Atom::WmTransientFor.as_ref()) {
@@ -210,6 +576,49 @@ impl Api {
         false
     }
 
+    /// The WM_CLIENT_LEADER of the target window if it has one.
+    ///
+    /// Applications with multiple top level windows set this property to group them together:
+    /// all windows sharing the same leader can be treated as a single unit.
+    pub fn client_leader(&self, id: WinId) -> Option<WinId> {
+        match self.get_prop(id, Atom::WmClientLeader.as_ref()) {
+            Ok(Prop::Window(leader)) if leader != 0 => Some(leader),
+            _ => None,
+        }
+    }
+
+    /// Check whether a compositing manager is currently running.
+    ///
+    /// Compositors advertise themselves by owning the `_NET_WM_CM_S0` selection.
+    pub fn compositor_running(&self) -> bool {
+        // xcb docs: https://www.mankier.com/3/xcb_get_selection_owner
+        xcb::get_selection_owner(&self.conn, self.known_atom(Atom::NetWmCmS0))
+            .get_reply()
+            .map(|r| r.owner() != xcb::NONE)
+            .unwrap_or(false)
+    }
+
+    /// Set the _NET_SYSTEM_TRAY_ORIENTATION property on the system tray host window.
+    ///
+    /// The host window is the current owner of the `_NET_SYSTEM_TRAY_S0` selection. Fails if
+    /// there is no system tray running.
+    pub fn set_systray_orientation(&self, horizontal: bool) -> Result<()> {
+        let cookie = xcb::get_selection_owner(&self.conn, self.known_atom(Atom::NetSystemTrayS0));
+        let host = cookie.get_reply()?.owner();
+        if host == xcb::NONE {
+            return Err(XcbError::QueryFailed("system tray owner"));
+        }
+
+        // _NET_SYSTEM_TRAY_ORIENTATION_HORZ == 0, _NET_SYSTEM_TRAY_ORIENTATION_VERT == 1
+        let orientation = if horizontal { 0 } else { 1 };
+        self.replace_prop(
+            host,
+            Atom::NetSystemTrayOrientation,
+            PropVal::Cardinal(&[orientation]),
+        );
+        Ok(())
+    }
+
     /// Get a handle on the underlying xcb connection
     pub fn conn(&self) -> &xcb::Connection {
         &self.conn
@@ -239,6 +648,51 @@ impl Api {
             .ok_or(XcbError::QueryFailed("visual type"))
     }
 
+    /// Look up a named color (e.g. "DodgerBlue") in the X color database and allocate it in the
+    /// default colormap of the root window, returning the resulting pixel value.
+    ///
+    /// Fails if the color name is not known to the X server.
+    pub fn alloc_named_color(&self, name: &str) -> Result<u32> {
+        let colormap = self.screen(0)?.default_colormap();
+        self.alloc_named_color_in(colormap, name)
+    }
+
+    /// Look up a named color in the X color database and allocate it in the colormap of the
+    /// target window, returning the resulting pixel value.
+    ///
+    /// Windows using a 32-bit ARGB visual (such as terminals with transparency enabled) have
+    /// their own colormap, so colors for them should be allocated here rather than using
+    /// [alloc_named_color][Api::alloc_named_color].
+    pub fn alloc_named_color_for_window(&self, id: WinId, name: &str) -> Result<u32> {
+        let colormap = self.window_colormap(id)?;
+        self.alloc_named_color_in(colormap, name)
+    }
+
+    fn alloc_named_color_in(&self, colormap: u32, name: &str) -> Result<u32> {
+        // xcb docs: https://www.mankier.com/3/xcb_alloc_named_color
+        Ok(xcb::alloc_named_color(&self.conn, colormap, name)
+            .get_reply()?
+            .pixel())
+    }
+
+    /// The colormap currently in use by the target window
+    pub fn window_colormap(&self, id: WinId) -> Result<u32> {
+        // xcb docs: https://www.mankier.com/3/xcb_get_window_attributes
+        Ok(xcb::get_window_attributes(&self.conn, id)
+            .get_reply()?
+            .colormap())
+    }
+
+    /// The pixel value for black in the default colormap of the root window.
+    pub fn black_pixel(&self) -> Result<u32> {
+        Ok(self.screen(0)?.black_pixel())
+    }
+
+    /// The pixel value for white in the default colormap of the root window.
+    pub fn white_pixel(&self) -> Result<u32> {
+        Ok(self.screen(0)?.white_pixel())
+    }
+
     /// Fetch the requested property for the target window
     pub fn get_prop(&self, id: WinId, name: &str) -> Result<Prop> {
         let atom = self.atom(name)?;
@@ -293,6 +747,100 @@ impl Api {
         })
     }
 
+    /// Fetch the raw 32bit values of the requested property for the target window.
+    ///
+    /// Unlike [get_prop][Api::get_prop], this does not attempt to interpret the property type
+    /// which makes it useful for reading properties that hold structured data such as lists of
+    /// cardinals. Fails if the property is not stored in 32bit format.
+    pub fn get_prop_raw(&self, id: WinId, name: &str) -> Result<Vec<u32>> {
+        let atom = self.atom(name)?;
+        let cookie = xcb::get_property(&self.conn, false, id, atom, xcb::ATOM_ANY, 0, 1024);
+        let r = cookie.get_reply()?;
+
+        if r.format() != 32 {
+            return Err(XcbError::InvalidPropertyData(format!(
+                "{} has a data format of {}: expected 32",
+                name,
+                r.format()
+            )));
+        }
+
+        Ok(r.value::<u32>().to_vec())
+    }
+
+    /// Any virtual root windows listed in `_NET_VIRTUAL_ROOTS` on the root window.
+    ///
+    /// Some remote desktop / VNC servers wrap the display in a virtual root which penrose does
+    /// not currently support managing windows within.
+    pub fn virtual_roots(&self) -> Vec<WinId> {
+        self.get_prop_raw(self.root, Atom::NetVirtualRoots.as_ref())
+            .unwrap_or_default()
+    }
+
+    /// The opaque regions of the target window as set in `_NET_WM_OPAQUE_REGION`.
+    ///
+    /// Regions are relative to the window itself. An empty Vec is returned if the property is
+    /// not set or is malformed.
+    pub fn opaque_region(&self, id: WinId) -> Vec<Region> {
+        match self.get_prop_raw(id, Atom::NetWmOpaqueRegion.as_ref()) {
+            Ok(vals) if vals.len() % 4 == 0 => vals
+                .chunks(4)
+                .map(|c| Region::new(c[0], c[1], c[2], c[3]))
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    /// The (left, right, top, bottom) extents of the invisible client side decorations (such as
+    /// drop shadows) drawn by GTK windows, as set in `_GTK_FRAME_EXTENTS`.
+    ///
+    /// Expanding a window's allocated region by these extents allows it to be tiled flush with
+    /// its neighbours.
+    pub fn gtk_frame_extents(&self, id: WinId) -> Option<(u32, u32, u32, u32)> {
+        match self.get_prop_raw(id, Atom::GtkFrameExtents.as_ref()) {
+            Ok(vals) if vals.len() == 4 => Some((vals[0], vals[1], vals[2], vals[3])),
+            _ => None,
+        }
+    }
+
+    /// The desktop names currently set in `_NET_DESKTOP_NAMES` on the root window.
+    ///
+    /// Returns an empty Vec if the property is not set.
+    pub fn desktop_names(&self) -> Vec<String> {
+        match self.get_prop(self.root, Atom::NetDesktopNames.as_ref()) {
+            Ok(Prop::UTF8String(names)) => names,
+            _ => vec![],
+        }
+    }
+
+    /// Fetch the `_NET_WM_NAME` title of each of the requested windows.
+    ///
+    /// All of the property requests are sent to the X server before any of the replies are read,
+    /// so this only requires a single round trip regardless of the number of windows. Windows
+    /// that do not have a title set (or that can not be queried) are omitted from the results.
+    pub fn titles(&self, ids: &[WinId]) -> Vec<(WinId, String)> {
+        let atom = self.known_atom(Atom::NetWmName);
+        let cookies: Vec<_> = ids
+            .iter()
+            .map(|&id| {
+                let cookie = xcb::get_property(&self.conn, false, id, atom, xcb::ATOM_ANY, 0, 1024);
+                (id, cookie)
+            })
+            .collect();
+
+        cookies
+            .into_iter()
+            .flat_map(|(id, cookie)| {
+                let r = cookie.get_reply().ok()?;
+                if r.value_len() == 0 {
+                    return None;
+                }
+                let title = String::from_utf8_lossy(r.value::<u8>());
+                Some((id, title.trim_matches('\0').to_string()))
+            })
+            .collect()
+    }
+
     /// Grab control of all keyboard input
     pub fn grab_keyboard(&self) -> Result<()> {
         xcb::grab_keyboard(
@@ -321,6 +869,10 @@ impl Api {
     /// If no event is currently available, None is returned.
     #[cfg(feature = "keysyms")]
     pub fn next_keypress(&self) -> Result<Option<KeyPressParseAttempt>> {
+        if let Some(e) = self.pending_events.borrow_mut().pop_front() {
+            return Ok(Some(self.queued_keypress(e?)));
+        }
+
         if let Some(event) = self.conn.poll_for_event() {
             let attempt = self.attempt_to_parse_as_keypress(event);
             if let Ok(Some(_)) = attempt {
@@ -335,6 +887,10 @@ impl Api {
     /// returning it as an [XKeySym] if it was a user keypress, or an [XEvent] if not.
     #[cfg(feature = "keysyms")]
     pub fn next_keypress_blocking(&self) -> Result<KeyPressParseAttempt> {
+        if let Some(e) = self.pending_events.borrow_mut().pop_front() {
+            return Ok(self.queued_keypress(e?));
+        }
+
         loop {
             if let Some(event) = self.conn.wait_for_event() {
                 let attempt = self.attempt_to_parse_as_keypress(event);
@@ -349,6 +905,20 @@ impl Api {
         }
     }
 
+    // Events queued by latest_configure_request have already been converted to XEvents
+    #[cfg(feature = "keysyms")]
+    fn queued_keypress(&self, event: XEvent) -> KeyPressParseAttempt {
+        if let XEvent::KeyPress { code, .. } = &event {
+            if let Some(s) = self.code_map.get(&(code.mask, code.code)) {
+                if let Ok(Ok(k)) = XKeySym::from_str(s).map(KeyPress::try_from) {
+                    return KeyPressParseAttempt::KeyPress(k);
+                }
+            }
+        }
+
+        KeyPressParseAttempt::XEvent(event)
+    }
+
     #[cfg(feature = "keysyms")]
     fn attempt_to_parse_as_keypress(
         &self,
@@ -377,11 +947,43 @@ impl Api {
 
         // Need to apply the randr_base mask as well which doesn't seem to work in 'match'
         if etype == self.randr_base + xcb::randr::NOTIFY {
+            let e: &xcb::randr::NotifyEvent = unsafe { xcb::cast_event(&event) };
+            if e.sub_code() == xcb::randr::NOTIFY_OUTPUT_CHANGE as u8 {
+                let oc = e.u().oc();
+                let connection = match oc.connection() as u32 {
+                    xcb::randr::CONNECTION_CONNECTED => OutputConnection::Connected,
+                    xcb::randr::CONNECTION_DISCONNECTED => OutputConnection::Disconnected,
+                    _ => OutputConnection::Unknown,
+                };
+                return Ok(Some(XEvent::OutputChange {
+                    output: oc.output(),
+                    connection,
+                    mode: oc.mode(),
+                }));
+            }
             return Ok(Some(XEvent::RandrNotify));
         } else if etype == self.randr_base + xcb::randr::SCREEN_CHANGE_NOTIFY {
             return Ok(Some(XEvent::ScreenChange));
         }
 
+        // All xkb events share a single event code so we need to check the xkb type as well
+        #[cfg(feature = "xkb")]
+        if etype == self.xkb_base {
+            let e: &xcb::xkb::StateNotifyEvent = unsafe { xcb::cast_event(&event) };
+            let group_changed = e.changed() & xcb::xkb::STATE_PART_GROUP_STATE as u16 != 0;
+            return Ok(match e.xkb_type() {
+                xcb::xkb::STATE_NOTIFY if group_changed => {
+                    Some(XEvent::KeyboardLayoutChange { group: e.group() })
+                }
+                xcb::xkb::INDICATOR_STATE_NOTIFY => {
+                    let e: &xcb::xkb::IndicatorStateNotifyEvent =
+                        unsafe { xcb::cast_event(&event) };
+                    Some(XEvent::KeyboardStateChange { leds: e.state() })
+                }
+                _ => None,
+            });
+        }
+
         Ok(match etype {
             xcb::BUTTON_PRESS | xcb::BUTTON_RELEASE | xcb::MOTION_NOTIFY => {
                 match MouseEvent::try_from(event) {
@@ -393,9 +995,13 @@ impl Api {
                 }
             }
 
-            xcb::KEY_PRESS => Some(XEvent::KeyPress(
-                KeyCode::try_from(event)?.ignoring_modifier(numlock),
-            )),
+            xcb::KEY_PRESS => {
+                let e: &xcb::KeyPressEvent = unsafe { xcb::cast_event(&event) };
+                Some(XEvent::KeyPress {
+                    code: KeyCode::from(e).ignoring_modifier(numlock),
+                    root: e.root(),
+                })
+            }
 
             xcb::MAP_REQUEST => {
                 let e: &xcb::MapRequestEvent = unsafe { xcb::cast_event(&event) };
@@ -415,6 +1021,7 @@ impl Api {
                     id: e.event(),
                     rpt: Point::new(e.root_x() as u32, e.root_y() as u32),
                     wpt: Point::new(e.event_x() as u32, e.event_y() as u32),
+                    time: e.time(),
                 })
             }
 
@@ -424,14 +1031,40 @@ impl Api {
                     id: e.event(),
                     rpt: Point::new(e.root_x() as u32, e.root_y() as u32),
                     wpt: Point::new(e.event_x() as u32, e.event_y() as u32),
+                    time: e.time(),
+                })
+            }
+
+            xcb::MAP_NOTIFY => {
+                let e: &xcb::MapNotifyEvent = unsafe { xcb::cast_event(&event) };
+                Some(XEvent::MapNotify {
+                    id: e.window(),
+                    override_redirect: e.override_redirect(),
                 })
             }
 
+            xcb::UNMAP_NOTIFY => {
+                let e: &xcb::UnmapNotifyEvent = unsafe { xcb::cast_event(&event) };
+                Some(XEvent::UnmapNotify { id: e.window() })
+            }
+
             xcb::DESTROY_NOTIFY => {
                 let e: &xcb::DestroyNotifyEvent = unsafe { xcb::cast_event(&event) };
                 Some(XEvent::Destroy { id: e.window() })
             }
 
+            xcb::CIRCULATE_NOTIFY => {
+                let e: &xcb::CirculateNotifyEvent = unsafe { xcb::cast_event(&event) };
+                Some(XEvent::CirculateNotify {
+                    id: e.window(),
+                    place: if e.place() == xcb::PLACE_ON_TOP as u8 {
+                        CirculatePlace::Top
+                    } else {
+                        CirculatePlace::Bottom
+                    },
+                })
+            }
+
             xcb::CONFIGURE_NOTIFY => {
                 let e: &xcb::ConfigureNotifyEvent = unsafe { xcb::cast_event(&event) };
                 Some(XEvent::ConfigureNotify {
@@ -493,6 +1126,13 @@ impl Api {
                     })
             }
 
+            xcb::SELECTION_CLEAR => {
+                let e: &xcb::SelectionClearEvent = unsafe { xcb::cast_event(&event) };
+                Some(XEvent::SelectionClear {
+                    selection: e.selection(),
+                })
+            }
+
             xcb::PROPERTY_NOTIFY => {
                 let e: &xcb::PropertyNotifyEvent = unsafe { xcb::cast_event(&event) };
                 xcb::xproto::get_atom_name(&self.conn, e.atom())
@@ -586,6 +1226,44 @@ impl Api {
         xcb::change_property(&self.conn, mode, id, a, ty, 32, data);
     }
 
+    /// Set the ICCCM WM_STATE property of a window along with an optional icon window that
+    /// can be used by taskbars when the window is iconified.
+    ///
+    /// See the ICCCM [spec][1] for details of the property format.
+    ///
+    /// [1]: https://www.x.org/releases/X11R7.6/doc/xorg-docs/specs/ICCCM/icccm.html#wm_state_property
+    pub fn set_wm_state(&self, id: WinId, state: WindowState, icon_window: Option<WinId>) {
+        let mode = xcb::PROP_MODE_REPLACE as u8;
+        let a = self.known_atom(Atom::WmState);
+        let val = match state {
+            WindowState::Withdrawn => 0,
+            WindowState::Normal => 1,
+            WindowState::Iconic => 3,
+        };
+        let icon = icon_window.unwrap_or(xcb::NONE);
+        xcb::change_property(&self.conn, mode, id, a, a, 32, &[val, icon]);
+    }
+
+    /// Set a CARDINAL property on a window by name, interning the atom if needed.
+    ///
+    /// This is useful for properties that penrose does not know about itself, such as custom
+    /// hints read by a compositor.
+    pub fn set_cardinal_prop(&self, id: WinId, name: &str, values: &[u32]) -> Result<()> {
+        let mode = xcb::PROP_MODE_REPLACE as u8;
+        let a = self.atom(name)?;
+        xcb::change_property(&self.conn, mode, id, a, xcb::ATOM_CARDINAL, 32, values);
+        Ok(())
+    }
+
+    /// Set a UTF8_STRING property on a window by name, interning the atom if needed.
+    pub fn set_string_prop(&self, id: WinId, name: &str, value: &str) -> Result<()> {
+        let mode = xcb::PROP_MODE_REPLACE as u8;
+        let a = self.atom(name)?;
+        let ty = self.known_atom(Atom::UTF8String);
+        xcb::change_property(&self.conn, mode, id, a, ty, 8, value.as_bytes());
+        Ok(())
+    }
+
     /// Create a new client window
     pub fn create_window(&self, ty: WinType, reg: Region, managed: bool) -> Result<WinId> {
         let (ty, mut data, class, root, depth, visual_id) = match ty {
@@ -674,30 +1352,257 @@ impl Api {
         Ok(id)
     }
 
-    /// Apply a set of config options to a window
-    pub fn configure_window(&self, id: WinId, conf: &[WinConfig]) {
-        let data: Vec<(u16, u32)> = conf.iter().flat_map::<Vec<_>, _>(|c| c.into()).collect();
-        xcb::configure_window(&self.conn, id, &data);
+    /// Create and map a new override-redirect dock window for use as a simple status bar.
+    ///
+    /// Text can be drawn to the bar using [draw_text][Api::draw_text].
+    pub fn create_bar(&self, region: Region) -> Result<WinId> {
+        self.create_window(WinType::InputOutput(Atom::NetWindowTypeDock), region, false)
     }
 
-    /// Destroy the X server state for a given window
-    pub fn destroy_window(&self, id: WinId) {
-        xcb::destroy_window(&self.conn, id);
-    }
+    /// Draw white on black text to the target window with its baseline starting at (x, y)
+    /// using the "fixed" X core font.
+    ///
+    /// This is intended for very simple status bars without needing to pull in cairo and pango
+    /// via the `xcb_draw` feature.
+    pub fn draw_text(&self, win: WinId, text: &str, x: i16, y: i16) -> Result<()> {
+        let gc = self.create_gc(win, 0xffff_ffff, 0xff00_0000, BAR_FONT)?;
+        // xcb docs: https://www.mankier.com/3/xcb_image_text_8
+        xcb::image_text_8(&self.conn, win, gc, x, y, text);
+        self.free_gc(gc);
+        self.flush();
 
-    /// Send a [XEvent::MapRequest] for the target window
-    pub fn map_window(&self, id: WinId) {
-        xcb::map_window(&self.conn, id);
+        Ok(())
     }
 
-    /// Mark the given window as currently having focus in the X server state
-    pub fn mark_focused_window(&self, id: WinId) {
-        // xcb docs: https://www.mankier.com/3/xcb_set_input_focus
-        xcb::set_input_focus(
-            &self.conn,                    // xcb connection to X11
-            xcb::INPUT_FOCUS_PARENT as u8, // focus the parent when focus is lost
-            id,                            // window to focus
-            0,                             // event time (0 == current time)
+    /// Clear the given region of the target window (relative to the window itself) to its
+    /// background so that it can be redrawn without needing to redraw the entire window.
+    pub fn clear_area(&self, win: WinId, r: Region) {
+        let (x, y, w, h) = r.values();
+        // xcb docs: https://www.mankier.com/3/xcb_clear_area
+        xcb::clear_area(
+            &self.conn, false, win, x as i16, y as i16, w as u16, h as u16,
+        );
+        self.flush();
+    }
+
+    /// Set the background of the root window to a solid color (as an RGB hex value).
+    ///
+    /// This is intended for minimal setups that are not running a separate wallpaper tool and
+    /// will be overwritten by any such tool that is run afterwards.
+    pub fn set_root_background(&self, color: u32) -> Result<()> {
+        let depth = self.screen(0)?.root_depth();
+        let pixmap = self.conn.generate_id();
+        // xcb docs: https://www.mankier.com/3/xcb_create_pixmap
+        xcb::create_pixmap(&self.conn, depth, pixmap, self.root, 1, 1);
+
+        let gc = self.conn.generate_id();
+        xcb::create_gc(&self.conn, gc, pixmap, &[(xcb::GC_FOREGROUND, color)]);
+        // xcb docs: https://www.mankier.com/3/xcb_poly_fill_rectangle
+        xcb::poly_fill_rectangle(&self.conn, pixmap, gc, &[xcb::Rectangle::new(0, 0, 1, 1)]);
+        xcb::free_gc(&self.conn, gc);
+
+        let data = &[(xcb::CW_BACK_PIXMAP, pixmap)];
+        xcb::change_window_attributes_checked(&self.conn, self.root, data).request_check()?;
+        // The server holds its own reference to the pixmap once it is set as the background
+        xcb::free_pixmap(&self.conn, pixmap);
+        // A width and height of 0 clears the entire window
+        xcb::clear_area(&self.conn, false, self.root, 0, 0, 0, 0);
+        self.flush();
+
+        Ok(())
+    }
+
+    /// The depth (bits per pixel) of the target window.
+    ///
+    /// Back buffers created using [create_pixmap][Api::create_pixmap] need to match the depth of
+    /// the window they will be copied to.
+    pub fn window_depth(&self, id: WinId) -> Result<u8> {
+        // xcb docs: https://www.mankier.com/3/xcb_get_geometry
+        Ok(xcb::get_geometry(&self.conn, id).get_reply()?.depth())
+    }
+
+    /// Create an off-screen pixmap that can be used as a back buffer for double buffered
+    /// drawing.
+    ///
+    /// Once drawn to, the contents of the pixmap can be displayed in a single operation using
+    /// [copy_area][Api::copy_area]. Pixmaps should be released using
+    /// [free_pixmap][Api::free_pixmap] once they are no longer needed.
+    pub fn create_pixmap(&self, width: u16, height: u16, depth: u8) -> u32 {
+        let pixmap = self.conn.generate_id();
+        // xcb docs: https://www.mankier.com/3/xcb_create_pixmap
+        xcb::create_pixmap(&self.conn, depth, pixmap, self.root, width, height);
+        pixmap
+    }
+
+    /// Release a pixmap created using [create_pixmap][Api::create_pixmap].
+    pub fn free_pixmap(&self, pixmap: u32) {
+        // xcb docs: https://www.mankier.com/3/xcb_free_pixmap
+        xcb::free_pixmap(&self.conn, pixmap);
+    }
+
+    /// Copy the given region of a pixmap to the same region of the target window.
+    ///
+    /// A single graphics context (created against the root window with graphics exposures
+    /// disabled) is shared by all copies, so both drawables need to have the same depth as
+    /// the root window.
+    pub fn copy_area(&self, src: u32, dst: WinId, r: Region) {
+        let (x, y, w, h) = r.values();
+        let gc = self.copy_gc();
+        // xcb docs: https://www.mankier.com/3/xcb_copy_area
+        xcb::copy_area(
+            &self.conn, src, dst, gc, x as i16, y as i16, x as i16, y as i16, w as u16, h as u16,
+        );
+        self.flush();
+    }
+
+    // The graphics context used by copy_area: created on first use and tracked along with
+    // those from create_gc so that it is released by free_all_gcs.
+    fn copy_gc(&self) -> u32 {
+        if let Some(gc) = self.copy_gc.get() {
+            return gc;
+        }
+
+        let gc = self.conn.generate_id();
+        // xcb docs: https://www.mankier.com/3/xcb_create_gc
+        xcb::create_gc(
+            &self.conn,
+            gc,
+            self.root,
+            &[(xcb::GC_GRAPHICS_EXPOSURES, 0)],
+        );
+        self.gcs.borrow_mut().insert(gc);
+        self.copy_gc.set(Some(gc));
+
+        gc
+    }
+
+    /// Create a new graphics context for drawing to the target window using the given
+    /// foreground and background colors and the named X core font.
+    ///
+    /// Fails if the font can not be opened. Fonts are shared with
+    /// [text_width][Api::text_width] and remain open until [close_fonts][Api::close_fonts] is
+    /// called. Graphics contexts should be released using [free_gc][Api::free_gc] once they are
+    /// no longer needed: any that are still live when [free_all_gcs][Api::free_all_gcs] is
+    /// called will be freed.
+    pub fn create_gc(&self, win: WinId, fg: u32, bg: u32, font: &str) -> Result<u32> {
+        let fid = self.cached_font(font)?;
+        let gc = self.conn.generate_id();
+        let values = &[
+            (xcb::GC_FOREGROUND, fg),
+            (xcb::GC_BACKGROUND, bg),
+            (xcb::GC_FONT, fid),
+        ];
+        // xcb docs: https://www.mankier.com/3/xcb_create_gc
+        xcb::create_gc(&self.conn, gc, win, values);
+        self.gcs.borrow_mut().insert(gc);
+
+        Ok(gc)
+    }
+
+    /// Free a graphics context previously created using [create_gc][Api::create_gc].
+    pub fn free_gc(&self, gc: u32) {
+        // xcb docs: https://www.mankier.com/3/xcb_free_gc
+        xcb::free_gc(&self.conn, gc);
+        self.gcs.borrow_mut().remove(&gc);
+        if self.copy_gc.get() == Some(gc) {
+            self.copy_gc.set(None);
+        }
+    }
+
+    /// Free all graphics contexts created using [create_gc][Api::create_gc] that have not
+    /// yet been freed.
+    pub fn free_all_gcs(&self) {
+        let gcs: Vec<u32> = self.gcs.borrow().iter().copied().collect();
+        gcs.into_iter().for_each(|gc| self.free_gc(gc));
+    }
+
+    /// The width in pixels of the given text when rendered using the named X core font.
+    ///
+    /// Fonts are opened on first use and then cached for subsequent queries until
+    /// [close_fonts][Api::close_fonts] is called. If the font can not be opened or the
+    /// extents can not be queried then a width of 0 is returned.
+    pub fn text_width(&self, font: &str, text: &str) -> u16 {
+        let fid = match self.cached_font(font) {
+            Ok(fid) => fid,
+            Err(e) => {
+                warn!("unable to open font {:?}: {}", font, e);
+                return 0;
+            }
+        };
+
+        let chars: Vec<xcb::Char2b> = text
+            .encode_utf16()
+            .map(|c| xcb::Char2b::new((c >> 8) as u8, c as u8))
+            .collect();
+
+        // xcb docs: https://www.mankier.com/3/xcb_query_text_extents
+        match xcb::query_text_extents(&self.conn, fid, &chars).get_reply() {
+            Ok(r) => r.overall_width().max(0).min(u16::MAX as i32) as u16,
+            Err(e) => {
+                warn!("unable to query text extents for {:?}: {}", text, e);
+                0
+            }
+        }
+    }
+
+    // Open the named font using a checked request so that a bad font name is reported to the
+    // caller rather than arriving later as an X error event.
+    fn cached_font(&self, font: &str) -> Result<u32> {
+        if let Some(fid) = self.fonts.borrow().get(font) {
+            return Ok(*fid);
+        }
+
+        let fid = self.conn.generate_id();
+        xcb::open_font_checked(&self.conn, fid, font).request_check()?;
+        self.fonts.borrow_mut().insert(font.into(), fid);
+
+        Ok(fid)
+    }
+
+    /// Close all fonts that have been cached by [text_width][Api::text_width] and
+    /// [create_gc][Api::create_gc].
+    ///
+    /// Any graphics contexts using these fonts should be freed first.
+    pub fn close_fonts(&self) {
+        self.fonts.borrow_mut().drain().for_each(|(_, fid)| {
+            xcb::close_font(&self.conn, fid);
+        });
+    }
+
+    /// Apply a set of config options to a window
+    pub fn configure_window(&self, id: WinId, conf: &[WinConfig]) {
+        let data: Vec<(u16, u32)> = conf.iter().flat_map::<Vec<_>, _>(|c| c.into()).collect();
+        xcb::configure_window(&self.conn, id, &data);
+    }
+
+    /// Raise the target window above all of its siblings without otherwise changing the stack
+    pub fn stack_above_all(&self, id: WinId) {
+        self.configure_window(id, &[WinConfig::StackAbove]);
+    }
+
+    /// Lower the target window below all of its siblings without otherwise changing the stack
+    pub fn stack_below_all(&self, id: WinId) {
+        self.configure_window(id, &[WinConfig::StackBelow]);
+    }
+
+    /// Destroy the X server state for a given window
+    pub fn destroy_window(&self, id: WinId) {
+        xcb::destroy_window(&self.conn, id);
+    }
+
+    /// Send a [XEvent::MapRequest] for the target window
+    pub fn map_window(&self, id: WinId) {
+        xcb::map_window(&self.conn, id);
+    }
+
+    /// Mark the given window as currently having focus in the X server state
+    pub fn mark_focused_window(&self, id: WinId) {
+        // xcb docs: https://www.mankier.com/3/xcb_set_input_focus
+        xcb::set_input_focus(
+            &self.conn,                    // xcb connection to X11
+            xcb::INPUT_FOCUS_PARENT as u8, // focus the parent when focus is lost
+            id,                            // window to focus
+            0,                             // event time (0 == current time)
         );
 
         self.replace_prop(self.root(), Atom::NetActiveWindow, PropVal::Window(&[id]));
@@ -721,11 +1626,75 @@ impl Api {
         Ok(reply.request_check()?)
     }
 
+    /// Select the X events that will be reported for the target window.
+    ///
+    /// `mask` is a combination of the `xcb::EVENT_MASK_*` flags and replaces the mask that was
+    /// previously selected for the window (including the default client mask applied when the
+    /// window was first managed), so include the defaults if you want to keep them. Commonly
+    /// useful flags are:
+    ///
+    ///   - `EVENT_MASK_ENTER_WINDOW` / `EVENT_MASK_LEAVE_WINDOW`: pointer crossing events
+    ///   - `EVENT_MASK_FOCUS_CHANGE`: FocusIn / FocusOut events
+    ///   - `EVENT_MASK_PROPERTY_CHANGE`: PropertyNotify events (title changes etc)
+    ///   - `EVENT_MASK_STRUCTURE_NOTIFY`: Configure / Map / Unmap / Destroy notify events
+    pub fn select_window_events(&self, id: WinId, mask: u32) -> Result<()> {
+        self.set_window_attributes(id, &[WinAttr::EventMask(mask)])
+    }
+
+    /// Set the border color of `focused` to `focused_color` and all windows in `others` to
+    /// `unfocused_color`.
+    ///
+    /// Unlike [set_window_attributes][Api::set_window_attributes], errors are not checked for
+    /// each window so that all of the updates can be sent in a single flush.
+    pub fn set_border_colors(
+        &self,
+        focused: WinId,
+        others: &[WinId],
+        focused_color: u32,
+        unfocused_color: u32,
+    ) {
+        let set_color = |id: WinId, color: u32| {
+            let data: Vec<(u32, u32)> = (&WinAttr::BorderColor(color)).into();
+            xcb::change_window_attributes(&self.conn, id, &data);
+        };
+
+        set_color(focused, focused_color);
+        others
+            .iter()
+            .filter(|&&id| id != focused)
+            .for_each(|&id| set_color(id, unfocused_color));
+
+        self.flush();
+    }
+
+    /// Remove the border from the target window entirely.
+    ///
+    /// Setting the border width to zero is not always enough on its own: some clients will
+    /// still show an artifact from an inherited border pixmap so this also sets a solid border
+    /// pixel, which replaces any border pixmap the window may have.
+    pub fn clear_border(&self, id: WinId) -> Result<()> {
+        self.set_window_attributes(id, &[WinAttr::BorderColor(0)])?;
+        self.configure_window(id, &[WinConfig::BorderPx(0)]);
+        Ok(())
+    }
+
     /// Unmap the target window
     pub fn unmap_window(&self, id: WinId) {
         xcb::unmap_window(&self.conn, id);
     }
 
+    /// Map all of the given windows, flushing once all of the requests have been sent
+    pub fn map_windows(&self, ids: &[WinId]) {
+        ids.iter().for_each(|&id| self.map_window(id));
+        self.flush();
+    }
+
+    /// Unmap all of the given windows, flushing once all of the requests have been sent
+    pub fn unmap_windows(&self, ids: &[WinId]) {
+        ids.iter().for_each(|&id| self.unmap_window(id));
+        self.flush();
+    }
+
     /// Find the current size and position of the target window
     pub fn window_geometry(&self, id: WinId) -> Result<Region> {
         let res = xcb::get_geometry(&self.conn, id).get_reply()?;
@@ -737,16 +1706,147 @@ impl Api {
         ))
     }
 
+    /// The width in pixels of the border currently set on the target window.
+    ///
+    /// Note that the region returned by [window_geometry][Api::window_geometry] does not include
+    /// the border.
+    pub fn border_width(&self, id: WinId) -> Result<u32> {
+        let res = xcb::get_geometry(&self.conn, id).get_reply()?;
+        Ok(res.border_width() as u32)
+    }
+
+    /// Apply the min, max and increment size hints of the target window to a proposed geometry.
+    ///
+    /// This should be used to constrain interactive resizes before calling
+    /// [position_window][crate::core::xconnection::XConn::position_window]. If the window has not set WM_NORMAL_HINTS then
+    /// the proposed region is returned unchanged.
+    pub fn clamp_resize(&self, id: WinId, proposed: Region) -> Region {
+        match self.get_prop(id, Atom::WmNormalHints.as_ref()) {
+            Ok(Prop::WmNormalHints(hints)) => hints.clamp(proposed),
+            _ => proposed,
+        }
+    }
+
+    /// The vendor and version details of the X server we are connected to.
+    ///
+    /// This can be used to apply workarounds for specific servers, such as XWayland.
+    pub fn server_info(&self) -> ServerInfo {
+        let setup = self.conn.get_setup();
+
+        ServerInfo {
+            vendor: setup.vendor().to_string(),
+            release: setup.release_number(),
+            major: setup.protocol_major_version(),
+            minor: setup.protocol_minor_version(),
+        }
+    }
+
+    /// The (bits per pixel, scanline pad, lsb first) image format used by the X server for
+    /// Z pixmap images of the given depth.
+    pub fn image_format(&self, depth: u8) -> Result<(u8, u8, bool)> {
+        let setup = self.conn.get_setup();
+        let lsb_first = setup.image_byte_order() == xcb::IMAGE_ORDER_LSB_FIRST as u8;
+
+        setup
+            .pixmap_formats()
+            .find(|f| f.depth() == depth)
+            .map(|f| (f.bits_per_pixel(), f.scanline_pad(), lsb_first))
+            .ok_or(XcbError::QueryFailed("pixmap format"))
+    }
+
+    /// Read the pixels currently displayed in the given region of the root window.
+    ///
+    /// Pixel data is converted from the format and byte order of the root visual into packed
+    /// RGBA bytes so that callers do not need to care about the layout used by the X server.
+    /// Only TrueColor and DirectColor visuals with at least 8 bits per pixel are supported: an
+    /// error is returned for anything else.
+    pub fn capture_region(&self, r: Region) -> Result<ImageData> {
+        let (x, y, w, h) = r.values();
+
+        // xcb docs: https://www.mankier.com/3/xcb_get_image
+        let reply = xcb::get_image(
+            &self.conn,
+            xcb::IMAGE_FORMAT_Z_PIXMAP as u8,
+            self.root,
+            x as i16,
+            y as i16,
+            w as u16,
+            h as u16,
+            !0, // all planes
+        )
+        .get_reply()?;
+
+        let (bpp, _, lsb_first) = self.image_format(reply.depth())?;
+        let screen = self.screen(0)?;
+        let visual = screen
+            .allowed_depths()
+            .flat_map(|d| d.visuals())
+            .find(|v| v.visual_id() == reply.visual())
+            .ok_or(XcbError::QueryFailed("root visual"))?;
+
+        let class = visual.class() as u32;
+        let masks = [visual.red_mask(), visual.green_mask(), visual.blue_mask()];
+        let supported_class =
+            class == xcb::VISUAL_CLASS_TRUE_COLOR || class == xcb::VISUAL_CLASS_DIRECT_COLOR;
+        if !supported_class || bpp < 8 || masks.contains(&0) {
+            return Err(XcbError::Raw(format!(
+                "unable to capture image data: unsupported visual (class={}, bpp={})",
+                class, bpp
+            )));
+        }
+
+        // Rows are padded out to the scanline pad of the image format
+        let raw = reply.data();
+        let bytes_per_pixel = (bpp / 8) as usize;
+        let stride = (raw.len() / (h as usize).max(1)).max(1);
+
+        let channel = |pixel: u32, mask: u32| -> u8 {
+            let max = mask >> mask.trailing_zeros();
+            (((pixel & mask) >> mask.trailing_zeros()) * 255 / max.max(1)) as u8
+        };
+
+        let mut data = Vec::with_capacity((w * h * 4) as usize);
+        for row in raw.chunks(stride).take(h as usize) {
+            for px in row.chunks(bytes_per_pixel).take(w as usize) {
+                let pixel = if lsb_first {
+                    px.iter().rev().fold(0, |acc, &b| (acc << 8) | b as u32)
+                } else {
+                    px.iter().fold(0, |acc, &b| (acc << 8) | b as u32)
+                };
+                data.extend_from_slice(&[
+                    channel(pixel, visual.red_mask()),
+                    channel(pixel, visual.green_mask()),
+                    channel(pixel, visual.blue_mask()),
+                    0xff,
+                ]);
+            }
+        }
+
+        Ok(ImageData { w, h, data })
+    }
+
+    /// The RandR version negotiated with the X server as a (major, minor) pair.
+    ///
+    /// This is queried once when the [Api] is initialised using `xcb::randr::query_version`:
+    /// querying again with a different client version would change the version in use for
+    /// the lifetime of the connection.
+    pub fn randr_version(&self) -> (u32, u32) {
+        self.randr_version
+    }
+
     // logic taken from https://github.com/rtbo/rust-xcb/blob/master/examples/randr_crtc_info.rs
     /// Query the randr API for current outputs and return the details as penrose
     /// [Screen] structs.
     pub fn current_screens(&self) -> Result<Vec<Screen>> {
         // xcb docs: https://www.mankier.com/3/xcb_randr_get_screen_resources
-        let resources = xcb::randr::get_screen_resources(&self.conn, self.check_win);
+        let resources = xcb::randr::get_screen_resources(&self.conn, self.check_win).get_reply()?;
+        let refresh_rates: HashMap<u32, u32> = resources
+            .modes()
+            .filter_map(|m| mode_refresh_mhz(&m).map(|mhz| (m.id(), mhz)))
+            .collect();
 
         // xcb docs: https://www.mankier.com/3/xcb_randr_get_crtc_info
         Ok(resources
-            .get_reply()?
             .crtcs()
             .iter()
             .flat_map(|c| xcb::randr::get_crtc_info(&self.conn, *c, 0).get_reply())
@@ -758,7 +1858,9 @@ impl Api {
                     r.width() as u32,
                     r.height() as u32,
                 );
-                Screen::new(region, i)
+                let mut s = Screen::new(region, i);
+                s.set_refresh_mhz(refresh_rates.get(&r.mode()).copied());
+                s
             })
             .filter(|s| {
                 let (_, _, w, _) = s.region(false).values();
@@ -767,12 +1869,442 @@ impl Api {
             .collect())
     }
 
+    /// A stable identifier for the current set of connected outputs.
+    ///
+    /// The fingerprint is made up of the name and resolution of each active output, sorted by
+    /// name, in the form `"DP-1:1920x1080,eDP-1:2560x1440"`. This can be used to detect when a
+    /// known arrangement of monitors (e.g. docked / undocked) is in use.
+    pub fn output_fingerprint(&self) -> Result<String> {
+        // xcb docs: https://www.mankier.com/3/xcb_randr_get_screen_resources
+        let resources = xcb::randr::get_screen_resources(&self.conn, self.check_win).get_reply()?;
+
+        // xcb docs: https://www.mankier.com/3/xcb_randr_get_output_info
+        let mut outputs: Vec<String> = resources
+            .outputs()
+            .iter()
+            .flat_map(|o| xcb::randr::get_output_info(&self.conn, *o, 0).get_reply())
+            .filter(|info| {
+                info.connection() == xcb::randr::CONNECTION_CONNECTED as u8
+                    && info.crtc() != xcb::NONE
+            })
+            .flat_map(|info| {
+                let crtc = xcb::randr::get_crtc_info(&self.conn, info.crtc(), 0)
+                    .get_reply()
+                    .ok()?;
+                let name = String::from_utf8_lossy(info.name());
+                Some(format!("{}:{}x{}", name, crtc.width(), crtc.height()))
+            })
+            .collect();
+
+        outputs.sort();
+        Ok(outputs.join(","))
+    }
+
+    /// Rotate the named output (e.g. "DP-1"), keeping its current mode and position.
+    ///
+    /// Once the rotation has been applied, [current_screens][Api::current_screens] will report
+    /// the rotated dimensions for the output. Note that the overall size of the X screen is not
+    /// changed so rotating an output will fail if the result would not fit within it.
+    pub fn set_output_rotation(&self, output: &str, rotation: Rotation) -> Result<()> {
+        let (id, crtc, config_timestamp) = self.active_output_crtc(output)?;
+        let rotation = match rotation {
+            Rotation::Normal => xcb::randr::ROTATION_ROTATE_0,
+            Rotation::Left => xcb::randr::ROTATION_ROTATE_90,
+            Rotation::Inverted => xcb::randr::ROTATION_ROTATE_180,
+            Rotation::Right => xcb::randr::ROTATION_ROTATE_270,
+        };
+
+        // xcb docs: https://www.mankier.com/3/xcb_randr_get_crtc_info
+        let info = xcb::randr::get_crtc_info(&self.conn, crtc, config_timestamp).get_reply()?;
+        if info.rotations() & rotation as u16 == 0 {
+            return Err(XcbError::Randr(format!(
+                "output {} does not support the requested rotation",
+                output
+            )));
+        }
+
+        let outputs = if info.outputs().is_empty() {
+            vec![id]
+        } else {
+            info.outputs().to_vec()
+        };
+
+        self.set_crtc_config(
+            crtc,
+            config_timestamp,
+            (info.x(), info.y()),
+            info.mode(),
+            rotation as u16,
+            &outputs,
+        )
+    }
+
+    /// The display modes supported by the named output (e.g. "DP-1").
+    ///
+    /// The [id][ModeInfo::id] of a returned mode can be passed to
+    /// [set_output_mode][Api::set_output_mode] to switch the output to that mode.
+    pub fn output_modes(&self, output: &str) -> Result<Vec<ModeInfo>> {
+        let (_, info, _) = self.find_output(output)?;
+        let supported = info.modes();
+
+        // xcb docs: https://www.mankier.com/3/xcb_randr_get_screen_resources
+        let resources = xcb::randr::get_screen_resources(&self.conn, self.check_win).get_reply()?;
+        Ok(resources
+            .modes()
+            .filter(|m| supported.contains(&m.id()))
+            .map(|m| ModeInfo {
+                id: m.id(),
+                w: m.width() as u32,
+                h: m.height() as u32,
+                refresh_mhz: mode_refresh_mhz(&m),
+            })
+            .collect())
+    }
+
+    /// Switch the named output to the given mode, keeping its current position and rotation.
+    ///
+    /// The mode must be one of those returned by [output_modes][Api::output_modes] for this
+    /// output.
+    pub fn set_output_mode(&self, output: &str, mode: u32) -> Result<()> {
+        let (id, info, config_timestamp) = self.find_output(output)?;
+        if !info.modes().contains(&mode) {
+            return Err(XcbError::Randr(format!(
+                "mode {} is not supported by output {}",
+                mode, output
+            )));
+        }
+        if info.crtc() == xcb::NONE {
+            return Err(XcbError::Randr(format!("output {} is not active", output)));
+        }
+
+        // xcb docs: https://www.mankier.com/3/xcb_randr_get_crtc_info
+        let crtc =
+            xcb::randr::get_crtc_info(&self.conn, info.crtc(), config_timestamp).get_reply()?;
+        let outputs = if crtc.outputs().is_empty() {
+            vec![id]
+        } else {
+            crtc.outputs().to_vec()
+        };
+
+        self.set_crtc_config(
+            info.crtc(),
+            config_timestamp,
+            (crtc.x(), crtc.y()),
+            mode,
+            crtc.rotation(),
+            &outputs,
+        )
+    }
+
+    /// Move the named output so that its top left corner is at (x, y) in the X screen, keeping its
+    /// current mode and rotation.
+    ///
+    /// The move is rejected if it would leave the active outputs as anything other than a single
+    /// connected block (i.e. if it would introduce a gap between outputs that would be included
+    /// in [virtual_screen_size][Api::virtual_screen_size]).
+    pub fn set_output_position(&self, output: &str, x: i16, y: i16) -> Result<()> {
+        if x < 0 || y < 0 {
+            return Err(XcbError::Randr(format!(
+                "invalid position for output {}: ({}, {})",
+                output, x, y
+            )));
+        }
+
+        let (_, crtc, config_timestamp) = self.active_output_crtc(output)?;
+
+        // xcb docs: https://www.mankier.com/3/xcb_randr_get_screen_resources
+        let resources = xcb::randr::get_screen_resources(&self.conn, self.check_win).get_reply()?;
+
+        // xcb docs: https://www.mankier.com/3/xcb_randr_get_crtc_info
+        let mut target = None;
+        let mut regions = Vec::new();
+        for &c in resources.crtcs() {
+            let info = xcb::randr::get_crtc_info(&self.conn, c, config_timestamp).get_reply()?;
+            if info.outputs().is_empty() || info.width() == 0 {
+                continue;
+            }
+            let (w, h) = (info.width() as u32, info.height() as u32);
+            if c == crtc {
+                regions.push(Region::new(x as u32, y as u32, w, h));
+                target = Some(info);
+            } else {
+                regions.push(Region::new(info.x() as u32, info.y() as u32, w, h));
+            }
+        }
+
+        let info = target
+            .ok_or_else(|| XcbError::Randr(format!("unable to find crtc for output {}", output)))?;
+
+        if !regions_are_contiguous(&regions) {
+            return Err(XcbError::Randr(format!(
+                "moving output {} to ({}, {}) would leave a gap between outputs",
+                output, x, y
+            )));
+        }
+
+        self.set_crtc_config(
+            crtc,
+            config_timestamp,
+            (x, y),
+            info.mode(),
+            info.rotation(),
+            info.outputs(),
+        )
+    }
+
+    /// Turn the named output on or off.
+    ///
+    /// Enabling an output that is currently off assigns it a free CRTC and applies its preferred
+    /// mode, positioned at the origin of the X screen. Disabling an output detaches it from its
+    /// CRTC. Either way the resulting RandR notification will cause the [WindowManager][1] to
+    /// re-detect its screens, moving workspaces off of any output that has been disabled.
+    /// Requesting the state that the output is already in is a no-op.
+    ///
+    /// [1]: crate::core::manager::WindowManager
+    pub fn set_output_enabled(&self, output: &str, enabled: bool) -> Result<()> {
+        let (id, info, config_timestamp) = self.find_output(output)?;
+        let active = info.crtc() != xcb::NONE;
+
+        match (enabled, active) {
+            (false, true) => self.set_crtc_config(
+                info.crtc(),
+                config_timestamp,
+                (0, 0),
+                xcb::NONE,
+                xcb::randr::ROTATION_ROTATE_0 as u16,
+                &[],
+            ),
+
+            (true, false) => {
+                // preferred modes (if any) are listed first
+                let mode = *info.modes().first().ok_or_else(|| {
+                    XcbError::Randr(format!("output {} has no available modes", output))
+                })?;
+
+                // xcb docs: https://www.mankier.com/3/xcb_randr_get_crtc_info
+                let crtc = info
+                    .crtcs()
+                    .iter()
+                    .copied()
+                    .find(|&c| {
+                        xcb::randr::get_crtc_info(&self.conn, c, config_timestamp)
+                            .get_reply()
+                            .map(|r| r.outputs().is_empty())
+                            .unwrap_or(false)
+                    })
+                    .ok_or_else(|| {
+                        XcbError::Randr(format!("no free crtc available for output {}", output))
+                    })?;
+
+                self.set_crtc_config(
+                    crtc,
+                    config_timestamp,
+                    (0, 0),
+                    mode,
+                    xcb::randr::ROTATION_ROTATE_0 as u16,
+                    &[id],
+                )
+            }
+
+            _ => Ok(()),
+        }
+    }
+
+    /// The current gamma ramp of the CRTC driving the named output.
+    ///
+    /// Returns `None` if the output is unknown, inactive or the ramp could not be read.
+    pub fn get_gamma(&self, output: &str) -> Option<GammaRamp> {
+        let (_, crtc, _) = self.active_output_crtc(output).ok()?;
+
+        // xcb docs: https://www.mankier.com/3/xcb_randr_get_crtc_gamma
+        let r = xcb::randr::get_crtc_gamma(&self.conn, crtc)
+            .get_reply()
+            .ok()?;
+        Some(GammaRamp {
+            red: r.red().to_vec(),
+            green: r.green().to_vec(),
+            blue: r.blue().to_vec(),
+        })
+    }
+
+    /// Set the gamma ramp of the CRTC driving the named output.
+    ///
+    /// Each channel of the ramp must have exactly the number of entries supported by the CRTC:
+    /// the simplest way to ensure this is to modify the ramp returned by
+    /// [get_gamma][Api::get_gamma].
+    pub fn set_gamma(&self, output: &str, ramp: &GammaRamp) -> Result<()> {
+        let (_, crtc, _) = self.active_output_crtc(output)?;
+
+        // xcb docs: https://www.mankier.com/3/xcb_randr_get_crtc_gamma_size
+        let size = xcb::randr::get_crtc_gamma_size(&self.conn, crtc)
+            .get_reply()?
+            .size() as usize;
+        if [&ramp.red, &ramp.green, &ramp.blue]
+            .iter()
+            .any(|c| c.len() != size)
+        {
+            return Err(XcbError::Randr(format!(
+                "gamma ramp for output {} must have {} entries per channel",
+                output, size
+            )));
+        }
+
+        // xcb docs: https://www.mankier.com/3/xcb_randr_set_crtc_gamma
+        xcb::randr::set_crtc_gamma_checked(&self.conn, crtc, &ramp.red, &ramp.green, &ramp.blue)
+            .request_check()?;
+        Ok(())
+    }
+
+    // Find the named output along with the config timestamp needed to modify it.
+    fn find_output(&self, output: &str) -> Result<(u32, xcb::randr::GetOutputInfoReply, u32)> {
+        // xcb docs: https://www.mankier.com/3/xcb_randr_get_screen_resources
+        let resources = xcb::randr::get_screen_resources(&self.conn, self.check_win).get_reply()?;
+        let config_timestamp = resources.config_timestamp();
+
+        // xcb docs: https://www.mankier.com/3/xcb_randr_get_output_info
+        for &id in resources.outputs() {
+            let info = xcb::randr::get_output_info(&self.conn, id, config_timestamp).get_reply()?;
+            if String::from_utf8_lossy(info.name()) == output {
+                return Ok((id, info, config_timestamp));
+            }
+        }
+
+        Err(XcbError::Randr(format!("unknown output: {}", output)))
+    }
+
+    // Find the named output and the CRTC currently driving it along with the config timestamp
+    // needed to modify it.
+    fn active_output_crtc(&self, output: &str) -> Result<(u32, u32, u32)> {
+        let (id, info, config_timestamp) = self.find_output(output)?;
+        if info.crtc() == xcb::NONE {
+            return Err(XcbError::Randr(format!("output {} is not active", output)));
+        }
+
+        Ok((id, info.crtc(), config_timestamp))
+    }
+
+    // Apply a new configuration to a CRTC, converting a non-success status into an error
+    fn set_crtc_config(
+        &self,
+        crtc: u32,
+        config_timestamp: u32,
+        (x, y): (i16, i16),
+        mode: u32,
+        rotation: u16,
+        outputs: &[u32],
+    ) -> Result<()> {
+        // xcb docs: https://www.mankier.com/3/xcb_randr_set_crtc_config
+        let reply = xcb::randr::set_crtc_config(
+            &self.conn,
+            crtc,
+            xcb::CURRENT_TIME,
+            config_timestamp,
+            x,
+            y,
+            mode,
+            rotation,
+            outputs,
+        )
+        .get_reply()?;
+
+        match reply.status() as u32 {
+            xcb::randr::SET_CONFIG_SUCCESS => Ok(()),
+            status => Err(XcbError::Randr(format!(
+                "unable to configure crtc {}: status {}",
+                crtc, status
+            ))),
+        }
+    }
+
     /// Query the randr API for current outputs and return the size of each screen
     pub fn screen_sizes(&self) -> Result<Vec<Region>> {
         self.current_screens()
             .map(|screens| screens.iter().map(|s| s.region(false)).collect())
     }
 
+    /// The bounding box of all current outputs.
+    ///
+    /// If the outputs do not tile perfectly (e.g. monitors of differing sizes or with a gap
+    /// between them) then the returned region will include the dead space that is not covered
+    /// by any output.
+    pub fn virtual_screen_size(&self) -> Result<Region> {
+        let regions = self.screen_sizes()?;
+        if regions.is_empty() {
+            return Err(XcbError::NoScreens);
+        }
+
+        let (x1, y1, x2, y2) = regions.iter().fold(
+            (u32::MAX, u32::MAX, 0, 0),
+            |(x1, y1, x2, y2), &Region { x, y, w, h }| {
+                (x1.min(x), y1.min(y), x2.max(x + w), y2.max(y + h))
+            },
+        );
+
+        Ok(Region::new(x1, y1, x2 - x1, y2 - y1))
+    }
+
+    /// The index of the output that lies in the given direction from the output at index
+    /// `current`, if there is one.
+    ///
+    /// See [Region::nearest_in_direction] for details of how the output is selected.
+    pub fn adjacent_output(
+        &self,
+        current: usize,
+        direction: RelativePosition,
+    ) -> Result<Option<usize>> {
+        let regions = self.screen_sizes()?;
+        let r = regions
+            .get(current)
+            .ok_or_else(|| XcbError::UnknownScreen(current, regions.len().saturating_sub(1)))?;
+
+        Ok(r.nearest_in_direction(direction, &regions))
+    }
+
+    /// The window from `candidates` that lies nearest to `from` in the given direction.
+    ///
+    /// Candidates are ranked by the distance between their center and the center of `from`
+    /// along the requested axis, with ties broken in favour of the window that has the largest
+    /// overlap with `from` on the perpendicular axis. Windows whose geometry can not be
+    /// determined are skipped.
+    pub fn window_in_direction(
+        &self,
+        from: WinId,
+        direction: RelativePosition,
+        candidates: &[WinId],
+    ) -> Option<WinId> {
+        let r = self.window_geometry(from).ok()?;
+        let p = r.midpoint();
+        let horizontal = matches!(direction, RelativePosition::Left | RelativePosition::Right);
+
+        let overlap =
+            |a1: u32, l1: u32, a2: u32, l2: u32| (a1 + l1).min(a2 + l2).saturating_sub(a1.max(a2));
+
+        candidates
+            .iter()
+            .filter(|&&id| id != from)
+            .flat_map(|&id| self.window_geometry(id).ok().map(|g| (id, g)))
+            .filter(|(_, g)| {
+                let q = g.midpoint();
+                match direction {
+                    RelativePosition::Left => q.x < p.x,
+                    RelativePosition::Right => q.x > p.x,
+                    RelativePosition::Above => q.y < p.y,
+                    RelativePosition::Below => q.y > p.y,
+                }
+            })
+            .min_by_key(|(_, g)| {
+                let q = g.midpoint();
+                if horizontal {
+                    let distance = (q.x as i64 - p.x as i64).abs();
+                    (distance, std::cmp::Reverse(overlap(r.y, r.h, g.y, g.h)))
+                } else {
+                    let distance = (q.y as i64 - p.y as i64).abs();
+                    (distance, std::cmp::Reverse(overlap(r.x, r.w, g.x, g.w)))
+                }
+            })
+            .map(|(id, _)| id)
+    }
+
     /// The list of currently active clients known to the X server
     pub fn current_clients(&self) -> Result<Vec<WinId>> {
         Ok(xcb::query_tree(&self.conn, self.root)
@@ -780,6 +2312,167 @@ impl Api {
             .map(|reply| reply.children().into())?)
     }
 
+    /// Top level windows that are currently mapped (viewable).
+    ///
+    /// Unlike [current_clients][Api::current_clients] this excludes unmapped and withdrawn
+    /// windows. Note that a window is only viewable if all of its ancestors are mapped.
+    pub fn mapped_windows(&self) -> Vec<WinId> {
+        self.top_level_windows_where(|_| true).unwrap_or_else(|e| {
+            warn!("unable to query mapped windows: {}", e);
+            vec![]
+        })
+    }
+
+    /// Top level override-redirect windows that are currently mapped.
+    ///
+    /// These are typically menus and popups that penrose does not manage directly.
+    pub fn mapped_override_redirect_windows(&self) -> Result<Vec<WinId>> {
+        self.top_level_windows_where(|r| r.override_redirect())
+    }
+
+    // Viewable top level windows whose attributes satisfy the given predicate
+    fn top_level_windows_where(
+        &self,
+        pred: impl Fn(&xcb::GetWindowAttributesReply) -> bool,
+    ) -> Result<Vec<WinId>> {
+        Ok(self
+            .current_clients()?
+            .into_iter()
+            .filter(|&id| {
+                // xcb docs: https://www.mankier.com/3/xcb_get_window_attributes
+                xcb::get_window_attributes(&self.conn, id)
+                    .get_reply()
+                    .map(|r| r.map_state() == xcb::MAP_STATE_VIEWABLE as u8 && pred(&r))
+                    .unwrap_or(false)
+            })
+            .collect())
+    }
+
+    /// The current stacking order of all top level windows, from bottom to top.
+    pub fn stacking_order(&self) -> Result<Vec<WinId>> {
+        // query_tree returns children in bottom to top stacking order
+        self.current_clients()
+    }
+
+    /// Restack the given windows so that they are in the order provided (bottom to top),
+    /// as returned by [stacking_order][Api::stacking_order].
+    ///
+    /// Each window is stacked directly above the one before it so the relative order of
+    /// the windows is restored exactly. Windows that no longer exist are skipped.
+    pub fn restore_stacking(&self, ordered: &[WinId]) {
+        let mut prev: Option<WinId> = None;
+        for &id in ordered {
+            let conf = match prev {
+                Some(sibling) => WinConfig::StackAboveSibling(sibling),
+                None => WinConfig::StackBelow,
+            };
+            let data: Vec<(u16, u32)> = (&conf).into();
+            // xcb docs: https://www.mankier.com/3/xcb_configure_window
+            match xcb::configure_window_checked(&self.conn, id, &data).request_check() {
+                Ok(_) => prev = Some(id),
+                Err(e) => warn!("unable to restack {}: {}", id, e),
+            }
+        }
+        self.flush();
+    }
+
+    /// Force connected monitors into the given DPMS power level.
+    ///
+    /// DPMS is enabled first if needed as the X server will only honour a forced power level
+    /// while it is enabled. Monitors will return to [DpmsMode::On] on the next user input.
+    #[cfg(feature = "dpms")]
+    pub fn set_dpms(&self, mode: DpmsMode) -> Result<()> {
+        let level = match mode {
+            DpmsMode::On => xcb::dpms::DPMS_MODE_ON,
+            DpmsMode::Standby => xcb::dpms::DPMS_MODE_STANDBY,
+            DpmsMode::Suspend => xcb::dpms::DPMS_MODE_SUSPEND,
+            DpmsMode::Off => xcb::dpms::DPMS_MODE_OFF,
+        };
+
+        if !self.dpms_info().enabled {
+            xcb::dpms::enable_checked(&self.conn).request_check()?;
+        }
+        xcb::dpms::force_level_checked(&self.conn, level as u16).request_check()?;
+        Ok(())
+    }
+
+    /// The current DPMS state of the X server.
+    ///
+    /// If the state can not be read (e.g. the DPMS extension is not available) then DPMS is
+    /// reported as disabled with monitors on.
+    #[cfg(feature = "dpms")]
+    pub fn dpms_info(&self) -> DpmsInfo {
+        match xcb::dpms::info(&self.conn).get_reply() {
+            Ok(r) => DpmsInfo {
+                enabled: r.state(),
+                mode: match r.power_level() as u32 {
+                    xcb::dpms::DPMS_MODE_STANDBY => DpmsMode::Standby,
+                    xcb::dpms::DPMS_MODE_SUSPEND => DpmsMode::Suspend,
+                    xcb::dpms::DPMS_MODE_OFF => DpmsMode::Off,
+                    _ => DpmsMode::On,
+                },
+            },
+            Err(e) => {
+                warn!("unable to query DPMS info: {}", e);
+                DpmsInfo {
+                    enabled: false,
+                    mode: DpmsMode::On,
+                }
+            }
+        }
+    }
+
+    /// The number of milliseconds since the last user input, as reported by the X Screensaver
+    /// extension.
+    ///
+    /// Returns `None` if the extension is not available.
+    #[cfg(feature = "screensaver")]
+    pub fn idle_time_ms(&self) -> Option<u64> {
+        // xcb docs: https://www.mankier.com/3/xcb_screensaver_query_info
+        xcb::screensaver::query_info(&self.conn, self.root)
+            .get_reply()
+            .map(|r| r.ms_since_user_input() as u64)
+            .ok()
+    }
+
+    /// The currently active layout group of the core keyboard.
+    ///
+    /// Groups are indexed from 0 in the order that layouts were configured (e.g. via
+    /// `setxkbmap -layout us,ru`).
+    #[cfg(feature = "xkb")]
+    pub fn keyboard_group(&self) -> Result<u8> {
+        let device = xcb::xkb::ID_USE_CORE_KBD as xcb::xkb::DeviceSpec;
+        Ok(xcb::xkb::get_state(&self.conn, device).get_reply()?.group())
+    }
+
+    /// The current state of the core keyboard LEDs as a bit mask.
+    ///
+    /// Each bit corresponds to an indicator defined by the active keymap: for most keymaps bit 0
+    /// is Caps Lock, bit 1 is Num Lock and bit 2 is Scroll Lock. Returns 0 if the state can not
+    /// be read. A [XEvent::KeyboardStateChange] is generated whenever this value changes.
+    #[cfg(feature = "xkb")]
+    pub fn keyboard_leds(&self) -> u32 {
+        let device = xcb::xkb::ID_USE_CORE_KBD as xcb::xkb::DeviceSpec;
+        match xcb::xkb::get_indicator_state(&self.conn, device).get_reply() {
+            Ok(r) => r.state(),
+            Err(e) => {
+                warn!("unable to query keyboard LED state: {}", e);
+                0
+            }
+        }
+    }
+
+    /// Lock the core keyboard to the requested layout group.
+    ///
+    /// A [XEvent::KeyboardLayoutChange] will be generated if this changes the active group.
+    #[cfg(feature = "xkb")]
+    pub fn set_keyboard_group(&self, group: u8) -> Result<()> {
+        let device = xcb::xkb::ID_USE_CORE_KBD as xcb::xkb::DeviceSpec;
+        xcb::xkb::latch_lock_state_checked(&self.conn, device, 0, 0, true, group, 0, false, 0)
+            .request_check()?;
+        Ok(())
+    }
+
     /// The current (x, y) position of the cursor relative to the root window
     pub fn cursor_position(&self) -> Point {
         xcb::query_pointer(&self.conn, self.root)
@@ -790,17 +2483,189 @@ impl Api {
             )
     }
 
+    /// The windows in `managed` whose midpoint currently lies within the given region.
+    ///
+    /// When an output is disconnected this can be used with the region it previously covered
+    /// to find windows that have been left stranded off-screen and need to be moved.
+    pub fn windows_on_region(&self, r: Region, managed: &[WinId]) -> Vec<WinId> {
+        managed
+            .iter()
+            .copied()
+            .filter(|&id| match self.window_geometry(id) {
+                Ok(g) => r.contains_point(&g.midpoint()),
+                Err(_) => false,
+            })
+            .collect()
+    }
+
+    /// The index of the screen in `screens` that currently contains the pointer.
+    ///
+    /// This is useful for determining which output should be used when there is no focused
+    /// window to go by. Returns 0 if the pointer is not within any of the given screens.
+    pub fn focused_output(&self, screens: &[Screen]) -> usize {
+        let p = self.cursor_position();
+        screens.iter().position(|s| s.contains(p)).unwrap_or(0)
+    }
+
+    /// Fetch the current pointer position along with the held mouse buttons and modifiers
+    /// in a single round trip.
+    ///
+    /// If the pointer can not be queried, the pointer is reported as being at the origin
+    /// with no buttons or modifiers held.
+    pub fn pointer_state(&self) -> PointerState {
+        // xcb docs: https://www.mankier.com/3/xcb_query_pointer
+        xcb::query_pointer(&self.conn, self.root)
+            .get_reply()
+            .map_or_else(
+                |_| PointerState {
+                    position: Point::new(0, 0),
+                    buttons: 0,
+                    mods: 0,
+                },
+                |reply| PointerState {
+                    position: Point::new(reply.root_x() as u32, reply.root_y() as u32),
+                    buttons: reply.mask() & 0x1f00,
+                    mods: reply.mask() & 0x00ff,
+                },
+            )
+    }
+
     /// Flush pending actions to the X event loop
     pub fn flush(&self) -> bool {
         self.conn.flush()
     }
 
+    /// Block until the X server has processed all requests sent so far.
+    ///
+    /// This forces a round trip to the server using a no-op `get_input_focus` request, acting
+    /// as a barrier for anything that needs to be sure that prior requests have taken effect.
+    ///
+    /// # Startup
+    /// Windows created between scanning for existing clients and selecting for substructure
+    /// events on the root window will otherwise be missed. The recommended sequence to close
+    /// this race is:
+    ///
+    /// 1. [grab_server][Api::grab_server]
+    /// 2. select events on the root window
+    /// 3. [sync][Api::sync]
+    /// 4. scan for existing windows using [current_clients][Api::current_clients]
+    /// 5. [ungrab_server][Api::ungrab_server]
+    pub fn sync(&self) -> Result<()> {
+        xcb::get_input_focus(&self.conn).get_reply()?;
+        Ok(())
+    }
+
+    /// Prevent processing of requests from all other client connections until
+    /// [ungrab_server][Api::ungrab_server] is called.
+    pub fn grab_server(&self) {
+        // xcb docs: https://www.mankier.com/3/xcb_grab_server
+        xcb::grab_server(&self.conn);
+    }
+
+    /// Resume processing of requests from other client connections
+    pub fn ungrab_server(&self) {
+        // xcb docs: https://www.mankier.com/3/xcb_ungrab_server
+        xcb::ungrab_server(&self.conn);
+        self.flush();
+    }
+
     /// The client that the X server currently considers to be focused
     pub fn focused_client(&self) -> Result<WinId> {
         // xcb docs: https://www.mankier.com/3/xcb_get_input_focus
         Ok(xcb::get_input_focus(&self.conn).get_reply()?.focus())
     }
 
+    /// Move input focus to the next viewable child of the given window, wrapping around to the
+    /// first child if focus is currently on the last child or outside of `parent`.
+    ///
+    /// This allows for cycling focus between the embedded sub-windows of a single client.
+    /// Returns the newly focused window, or `None` if `parent` has no focusable children.
+    pub fn focus_next_child(&self, parent: WinId) -> Option<WinId> {
+        // xcb docs: https://www.mankier.com/3/xcb_query_tree
+        let children: Vec<WinId> = xcb::query_tree(&self.conn, parent)
+            .get_reply()
+            .ok()?
+            .children()
+            .iter()
+            .copied()
+            .filter(|&id| {
+                // xcb docs: https://www.mankier.com/3/xcb_get_window_attributes
+                xcb::get_window_attributes(&self.conn, id)
+                    .get_reply()
+                    .map(|r| {
+                        r.map_state() == xcb::MAP_STATE_VIEWABLE as u8
+                            && r.class() == xcb::WINDOW_CLASS_INPUT_OUTPUT as u16
+                    })
+                    .unwrap_or(false)
+            })
+            .collect();
+
+        let current = self.focused_client().ok();
+        let next = match children.iter().position(|&id| Some(id) == current) {
+            Some(ix) => children[(ix + 1) % children.len()],
+            None => *children.first()?,
+        };
+
+        // xcb docs: https://www.mankier.com/3/xcb_set_input_focus
+        xcb::set_input_focus(&self.conn, xcb::INPUT_FOCUS_PARENT as u8, next, 0);
+        self.flush();
+
+        Some(next)
+    }
+
+    /// The top level window (a direct child of the root window) containing the window that
+    /// currently holds input focus.
+    ///
+    /// [focused_client][Api::focused_client] may return a sub-window of a client, which will not
+    /// match any of the window IDs that are being managed.
+    pub fn focused_toplevel(&self) -> Option<WinId> {
+        self.toplevel_for(self.focused_client().ok()?)
+    }
+
+    // Walk up the window tree from id until we reach a direct child of the root window
+    fn toplevel_for(&self, mut id: WinId) -> Option<WinId> {
+        if id == xcb::NONE || id == xcb::INPUT_FOCUS_POINTER_ROOT || id == self.root {
+            return None;
+        }
+
+        loop {
+            // xcb docs: https://www.mankier.com/3/xcb_query_tree
+            let parent = xcb::query_tree(&self.conn, id).get_reply().ok()?.parent();
+            if parent == self.root {
+                return Some(id);
+            } else if parent == xcb::NONE {
+                return None;
+            }
+            id = parent;
+        }
+    }
+
+    /// Re-focus `expected` if the X server reports that some other top level window now holds
+    /// focus.
+    ///
+    /// Applications are able to take focus without going through the window manager so this can
+    /// be used to undo focus changes that were not requested by penrose. Focus being held by a
+    /// sub-window of `expected` is not treated as a steal, and neither is focus being set to
+    /// `None` or `PointerRoot` as no other client holds it. Returns true if focus needed to be
+    /// reasserted.
+    ///
+    /// penrose does not currently select for `FocusOut` events so this is not called
+    /// automatically: it is up to the caller to decide when focus should be checked.
+    pub fn reassert_focus(&self, expected: WinId) -> Result<bool> {
+        let focus = self.focused_client()?;
+        if focus == xcb::NONE || focus == xcb::INPUT_FOCUS_POINTER_ROOT {
+            return Ok(false);
+        }
+
+        if self.toplevel_for(focus) == Some(expected) {
+            return Ok(false);
+        }
+
+        self.mark_focused_window(expected);
+        self.flush();
+        Ok(true)
+    }
+
     /// Register intercepts for each given [KeyCode]
     pub fn grab_keys(&self, keys: &[&KeyCode]) {
         // We need to explicitly grab NumLock as an additional modifier and then drop it later on
@@ -826,6 +2691,58 @@ impl Api {
         self.flush();
     }
 
+    /// Grab a single [KeyCode] on the given window rather than the root window.
+    ///
+    /// The grab is only active while the window (or one of its children) has input focus,
+    /// allowing for bindings that only apply to a particular window. As with
+    /// [grab_keys][Api::grab_keys], the key is also grabbed with NumLock active.
+    pub fn grab_key_on(&self, win: WinId, code: KeyCode) {
+        let mode = xcb::GRAB_MODE_ASYNC as u8;
+        for m in &[0, xcb::MOD_MASK_2 as u16] {
+            // xcb docs: https://www.mankier.com/3/xcb_grab_key
+            xcb::grab_key(&self.conn, false, win, code.mask | m, code.code, mode, mode);
+        }
+        self.flush();
+    }
+
+    /// Release a grab made using [grab_key_on][Api::grab_key_on].
+    pub fn ungrab_key_on(&self, win: WinId, code: KeyCode) {
+        for m in &[0, xcb::MOD_MASK_2 as u16] {
+            // xcb docs: https://www.mankier.com/3/xcb_ungrab_key
+            xcb::ungrab_key(&self.conn, code.code, win, code.mask | m);
+        }
+        self.flush();
+    }
+
+    /// Check whether the given [KeyCode] is already grabbed by another X client.
+    ///
+    /// X does not provide a way to query existing grabs directly so this attempts the grab and
+    /// checks for a BadAccess error, immediately releasing the grab if it succeeded.
+    ///
+    /// NOTE: this should be called before [grab_keys][Api::grab_keys] as probing a key that we
+    /// have already grabbed ourselves will release our grab.
+    pub fn probe_grab(&self, code: KeyCode) -> bool {
+        let mode = xcb::GRAB_MODE_ASYNC as u8;
+        let res = xcb::grab_key_checked(
+            &self.conn, false, self.root, code.mask, code.code, mode, mode,
+        )
+        .request_check();
+
+        match res {
+            Err(e) if e.error_code() == xcb::ACCESS => true,
+            Err(e) => {
+                warn!("unexpected error probing grab for {:?}: {}", code, e);
+                false
+            }
+            Ok(_) => {
+                // xcb docs: https://www.mankier.com/3/xcb_ungrab_key
+                xcb::ungrab_key(&self.conn, code.code, self.root, code.mask);
+                self.flush();
+                false
+            }
+        }
+    }
+
     /// Register intercepts for each given [MouseState]
     pub fn grab_mouse_buttons(&self, states: &[&MouseState]) {
         // We need to explicitly grab NumLock as an additional modifier and then drop it later on
@@ -901,6 +2818,10 @@ impl Api {
     /// This method handles all of the mapping of xcb events to penrose [XEvent] instances,
     /// returning an Error when the event channel from the X server is closed.
     pub fn wait_for_event(&self) -> Result<XEvent> {
+        if let Some(e) = self.pending_events.borrow_mut().pop_front() {
+            return e;
+        }
+
         loop {
             if let Some(event) = self.conn.wait_for_event() {
                 // Got an event but it might not be one we care about / know how to handle
@@ -922,6 +2843,10 @@ impl Api {
     /// returning None if there is no pending event and an error if the connection to the X server
     /// is closed.
     pub fn poll_for_event(&self) -> Result<Option<XEvent>> {
+        if let Some(e) = self.pending_events.borrow_mut().pop_front() {
+            return e.map(Some);
+        }
+
         if let Some(event) = self.conn.poll_for_event() {
             self.generic_xcb_to_xevent(event)
         } else {
@@ -929,9 +2854,107 @@ impl Api {
         }
     }
 
+    /// Drain any configure requests for the target window that have already been received from
+    /// the X server, returning the region requested by the most recent one.
+    ///
+    /// Clients that are being resized interactively (video players in particular) can send a
+    /// large number of configure requests in quick succession: honouring only the latest avoids
+    /// repeatedly repositioning the window. All other events are preserved and will be returned
+    /// from [wait_for_event][Api::wait_for_event] and [poll_for_event][Api::poll_for_event] in
+    /// the order they were received, including any errors reported by the X server.
+    pub fn latest_configure_request(&self, id: WinId) -> Option<Region> {
+        let mut latest = None;
+        let mut pending = self.pending_events.borrow_mut();
+
+        pending.retain(|e| match e {
+            Ok(XEvent::ConfigureRequest { id: target, r, .. }) if *target == id => {
+                latest = Some(*r);
+                false
+            }
+            _ => true,
+        });
+
+        while let Some(event) = self.conn.poll_for_event() {
+            match self.generic_xcb_to_xevent(event) {
+                Ok(Some(XEvent::ConfigureRequest { id: target, r, .. })) if target == id => {
+                    latest = Some(r)
+                }
+                Ok(Some(e)) => pending.push_back(Ok(e)),
+                Ok(None) => (),
+                // Stop draining so that the error is delivered in order
+                Err(e) => {
+                    pending.push_back(Err(e));
+                    break;
+                }
+            }
+        }
+
+        latest
+    }
+
     /// Move the cursor to the given (x, y) position inside the specified window.
     pub fn warp_cursor(&self, id: WinId, x: usize, y: usize) {
         // conn source target source(x y w h) dest(x y)
         xcb::warp_pointer(&self.conn, 0, id, 0, 0, 0, 0, x as i16, y as i16);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DB: &str = "\
+! a comment: with a colon
+Xft.dpi: 96
+  Xft.antialias  :   true\t
+no colon here
+URxvt.font: xft:Hack:size=10
+Xft.dpi: 120
+! Xft.hinting: true
+Xft.hintstyle: hint\\
+slight
+";
+
+    test_cases! {
+        parse_resource_lookups;
+        args: (name: &str, expected: Option<&str>);
+
+        case: simple => ("URxvt.font", Some("xft:Hack:size=10"));
+        case: whitespace_is_trimmed => ("Xft.antialias", Some("true"));
+        case: last_definition_wins => ("Xft.dpi", Some("120"));
+        case: commented_out => ("Xft.hinting", None);
+        case: comment_text_is_not_a_key => ("! a comment", None);
+        case: lines_without_a_colon_are_skipped => ("no colon here", None);
+        case: line_continuations_are_joined => ("Xft.hintstyle", Some("hintslight"));
+        case: missing => ("Xft.rgba", None);
+
+        body: {
+            assert_eq!(parse_resource(DB, name), expected.map(String::from));
+        }
+    }
+
+    fn r(x: u32, y: u32, w: u32, h: u32) -> Region {
+        Region::new(x, y, w, h)
+    }
+
+    test_cases! {
+        regions_contiguity;
+        args: (regions: Vec<Region>, expected: bool);
+
+        case: single => (vec![r(0, 0, 1920, 1080)], true);
+        case: edge_adjacent_horizontal => (vec![r(0, 0, 1920, 1080), r(1920, 0, 1280, 1024)], true);
+        case: edge_adjacent_vertical => (vec![r(0, 0, 1920, 1080), r(0, 1080, 1920, 1080)], true);
+        case: partial_edge => (vec![r(0, 0, 1920, 1080), r(1920, 500, 1280, 1024)], true);
+        case: corner_only => (vec![r(0, 0, 1920, 1080), r(1920, 1080, 1280, 1024)], false);
+        case: overlapping => (vec![r(0, 0, 1920, 1080), r(1000, 500, 1920, 1080)], true);
+        case: disjoint => (vec![r(0, 0, 1920, 1080), r(2000, 0, 1280, 1024)], false);
+        case: chained_through_middle => (
+            vec![r(0, 0, 100, 100), r(200, 0, 100, 100), r(100, 0, 100, 100)],
+            true
+        );
+
+        body: {
+            assert_eq!(regions_are_contiguous(&regions), expected);
+        }
+    }
+}
@@ -188,6 +188,13 @@ impl From<&WinConfig> for Vec<(u16, u32)> {
             WinConfig::StackAbove => {
                 vec![(xcb::CONFIG_WINDOW_STACK_MODE as u16, xcb::STACK_MODE_ABOVE)]
             }
+            WinConfig::StackAboveSibling(sibling) => vec![
+                (xcb::CONFIG_WINDOW_SIBLING as u16, *sibling),
+                (xcb::CONFIG_WINDOW_STACK_MODE as u16, xcb::STACK_MODE_ABOVE),
+            ],
+            WinConfig::StackBelow => {
+                vec![(xcb::CONFIG_WINDOW_STACK_MODE as u16, xcb::STACK_MODE_BELOW)]
+            }
         }
     }
 }
@@ -207,6 +214,7 @@ impl From<&WinAttr> for Vec<(u32, u32)> {
         match w {
             WinAttr::BorderColor(c) => vec![(xcb::CW_BORDER_PIXEL, *c)],
             WinAttr::ClientEventMask => vec![(xcb::CW_EVENT_MASK, client_event_mask)],
+            WinAttr::EventMask(mask) => vec![(xcb::CW_EVENT_MASK, *mask)],
             WinAttr::RootEventMask => vec![(xcb::CW_EVENT_MASK, root_event_mask)],
         }
     }
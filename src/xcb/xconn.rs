@@ -13,13 +13,14 @@
  */
 use crate::{
     core::{
-        bindings::{KeyBindings, MouseBindings},
+        bindings::{KeyBindings, KeyCode, MouseBindings},
+        config::Config,
         data_types::{Point, PropVal, Region, WinAttr, WinConfig, WinId, WinType},
         manager::WindowManager,
         screen::Screen,
         xconnection::{
-            Atom, Prop, XConn, XEvent, AUTO_FLOAT_WINDOW_TYPES, EWMH_SUPPORTED_ATOMS,
-            UNMANAGED_WINDOW_TYPES,
+            Atom, Prop, WindowState, XConn, XEvent, AUTO_FLOAT_WINDOW_TYPES, EWMH_SUPPORTED_ATOMS,
+            NO_BORDER_WINDOW_TYPES, UNMANAGED_WINDOW_TYPES,
         },
     },
     draw::Color,
@@ -27,9 +28,24 @@ use crate::{
     Result,
 };
 
-use std::collections::HashMap;
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+};
 
 const WM_NAME: &str = "penrose";
+const LAYOUT_STATE_PROP_PREFIX: &str = "_PENROSE_LAYOUT_STATE_";
+const FLOATING_PROP: &str = "_PENROSE_FLOATING";
+
+// The interned ids for the (auto float, unmanaged, no border) window types
+fn window_type_atoms(api: &Api) -> (Vec<u32>, Vec<u32>, Vec<u32>) {
+    let ids = |atoms: &[Atom]| atoms.iter().map(|a| api.known_atom(*a)).collect();
+    (
+        ids(AUTO_FLOAT_WINDOW_TYPES),
+        ids(UNMANAGED_WINDOW_TYPES),
+        ids(NO_BORDER_WINDOW_TYPES),
+    )
+}
 
 /**
  * Handles communication with an X server via the XCB library.
@@ -41,35 +57,101 @@ const WM_NAME: &str = "penrose";
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct XcbConnection {
     api: Api,
-    check_win: WinId,
+    check_win: Cell<WinId>,
     auto_float_types: Vec<u32>,
     dont_manage_types: Vec<u32>,
+    no_border_types: Vec<u32>,
+    saved_geometry: RefCell<HashMap<WinId, Region>>,
+    floating_geometry: RefCell<HashMap<WinId, Region>>,
+    has_border: RefCell<HashMap<WinId, bool>>,
+    outputs: RefCell<Vec<Region>>,
+    grabbed_keys: RefCell<Vec<KeyCode>>,
+    grabbed_buttons: RefCell<Vec<(u8, u16)>>,
+    current_workspace: Cell<usize>,
+    workspace_focus: RefCell<HashMap<usize, WinId>>,
+    above_fullscreen: RefCell<Vec<WinId>>,
+    unmap_override_redirect_on_cleanup: bool,
+    dry_run: bool,
 }
 
 impl XcbConnection {
     /// Establish a new connection to the running X server. Fails if unable to connect
     pub fn new() -> Result<Self> {
         let api = Api::new()?;
-        let auto_float_types: Vec<u32> = AUTO_FLOAT_WINDOW_TYPES
-            .iter()
-            .map(|a| api.known_atom(*a))
-            .collect();
-        let dont_manage_types: Vec<u32> = UNMANAGED_WINDOW_TYPES
-            .iter()
-            .map(|a| api.known_atom(*a))
-            .collect();
+        let (auto_float_types, dont_manage_types, no_border_types) = window_type_atoms(&api);
 
         api.set_randr_notify_mask()?;
         let check_win = api.create_window(WinType::CheckWin, Region::new(0, 0, 1, 1), false)?;
 
         Ok(Self {
             api,
-            check_win,
+            check_win: Cell::new(check_win),
             auto_float_types,
             dont_manage_types,
+            no_border_types,
+            saved_geometry: RefCell::new(HashMap::new()),
+            floating_geometry: RefCell::new(HashMap::new()),
+            has_border: RefCell::new(HashMap::new()),
+            outputs: RefCell::new(Vec::new()),
+            grabbed_keys: RefCell::new(Vec::new()),
+            grabbed_buttons: RefCell::new(Vec::new()),
+            current_workspace: Cell::new(0),
+            workspace_focus: RefCell::new(HashMap::new()),
+            above_fullscreen: RefCell::new(Vec::new()),
+            unmap_override_redirect_on_cleanup: false,
+            dry_run: false,
         })
     }
 
+    /// Enable or disable dry run mode for this connection.
+    ///
+    /// While in dry run mode, all [XConn] methods that would modify the state of the X server
+    /// (positioning windows, setting focus, updating properties etc) are logged at `info` level
+    /// instead of being sent to the server. Queries are still answered by the real X server,
+    /// allowing you to preview what a given config would do to your current windows.
+    ///
+    /// NOTE: [XConn::init] still registers for root window events so that penrose is able to
+    /// receive events from the server.
+    pub fn set_dry_run(&mut self, dry_run: bool) {
+        self.dry_run = dry_run;
+    }
+
+    /// Whether or not this connection is currently in dry run mode.
+    ///
+    /// See [set_dry_run][XcbConnection::set_dry_run] for details.
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    /// Enable or disable unmapping of any remaining override-redirect windows (such as menus
+    /// that were left open) when [XConn::cleanup] is called.
+    ///
+    /// This is disabled by default.
+    pub fn set_unmap_override_redirect_on_cleanup(&mut self, unmap: bool) {
+        self.unmap_override_redirect_on_cleanup = unmap;
+    }
+
+    // Log the requested call and return true if it should be skipped
+    fn dry_run(&self, call: impl FnOnce() -> String) -> bool {
+        if self.dry_run {
+            info!("dry run: {}", call());
+        }
+        self.dry_run
+    }
+
+    /// Re-intern all known [Atom] values and refresh any cached state derived from them.
+    ///
+    /// See [Api::reintern_atoms] for details.
+    pub fn reintern_atoms(&mut self) -> Result<()> {
+        self.api.reintern_atoms()?;
+        let (auto_float_types, dont_manage_types, no_border_types) = window_type_atoms(&self.api);
+        self.auto_float_types = auto_float_types;
+        self.dont_manage_types = dont_manage_types;
+        self.no_border_types = no_border_types;
+        self.has_border.borrow_mut().clear();
+        Ok(())
+    }
+
     fn window_has_type_in(&self, id: WinId, win_types: &[u32]) -> bool {
         if let Ok(Prop::Atom(atoms)) = self.api.get_prop(id, Atom::NetWmWindowType.as_ref()) {
             atoms.iter().any(|atom| {
@@ -84,6 +166,231 @@ impl XcbConnection {
         }
     }
 
+    /// Append a new desktop with the given name to the EWMH desktop properties on the root window.
+    ///
+    /// The current `_NET_NUMBER_OF_DESKTOPS` is respected, with any unnamed desktops being given
+    /// an empty name.
+    pub fn add_desktop(&self, name: &str) -> Result<()> {
+        let root = self.api.root();
+        let mut names = match self.api.get_prop(root, Atom::NetDesktopNames.as_ref()) {
+            Ok(Prop::UTF8String(strs)) => strs,
+            _ => vec![],
+        };
+        let n = match self.api.get_prop(root, Atom::NetNumberOfDesktops.as_ref()) {
+            Ok(Prop::Cardinal(n)) => n as usize,
+            _ => names.len(),
+        };
+
+        names.resize(n, String::new());
+        names.push(name.to_string());
+        self.update_desktops(&names.iter().map(|s| s.as_str()).collect::<Vec<_>>());
+
+        Ok(())
+    }
+
+    /// Toggle a window between its current position and floating in the center of `screen`,
+    /// returning the new floating state of the window.
+    ///
+    /// When floating, the current geometry of the window is stored and the window is resized to
+    /// 60% of the effective region of the screen. Toggling back re-applies the stored geometry.
+    /// This is tracked separately from the geometry stored using
+    /// [remember_geometry][XcbConnection::remember_geometry] so floating and fullscreen windows
+    /// do not interfere with one another.
+    pub fn toggle_float(&self, id: WinId, screen: &Screen) -> bool {
+        let floating = !self.floating_geometry.borrow().contains_key(&id);
+        if self.dry_run(|| format!("toggle_float({}, {:?})", id, screen.region(true))) {
+            return floating;
+        }
+
+        if !floating {
+            if let Some(r) = self.floating_geometry.borrow_mut().remove(&id) {
+                self.api.configure_window(id, &[WinConfig::Position(r)]);
+            }
+            return false;
+        }
+
+        match self.api.window_geometry(id) {
+            Ok(r) => self.floating_geometry.borrow_mut().insert(id, r),
+            Err(e) => {
+                warn!("unable to store geometry for {} before floating: {}", id, e);
+                return false;
+            }
+        };
+
+        let r = screen.region(true).relative_sub_region(0.2, 0.2, 0.6, 0.6);
+        self.api
+            .configure_window(id, &[WinConfig::Position(r), WinConfig::StackAbove]);
+        true
+    }
+
+    /// Position a window using offsets and sizes given as fractions (0.0 to 1.0) of the
+    /// effective region of `screen`.
+    ///
+    /// See [Region::relative_sub_region] for details.
+    #[allow(clippy::too_many_arguments)]
+    pub fn position_window_relative(
+        &self,
+        id: WinId,
+        screen: &Screen,
+        x_pct: f64,
+        y_pct: f64,
+        w_pct: f64,
+        h_pct: f64,
+        border: u32,
+    ) {
+        let r = screen
+            .region(true)
+            .relative_sub_region(x_pct, y_pct, w_pct, h_pct);
+        self.position_window(id, r, border, false);
+    }
+
+    /// Warp the cursor to `target` only if focus is moving between two different outputs.
+    ///
+    /// This keeps the cursor with keyboard focus when it moves to another monitor (preventing
+    /// focus-follows-mouse from pulling focus back) without warping the cursor around needlessly
+    /// when moving focus within a single monitor.
+    pub fn warp_cursor_if_output_changed(
+        &self,
+        target: WinId,
+        from_screen: usize,
+        to_screen: usize,
+        screens: &[Screen],
+    ) {
+        if from_screen == to_screen {
+            return;
+        }
+
+        match screens.get(to_screen) {
+            Some(s) => self.warp_cursor(Some(target), s),
+            None => warn!("unable to warp cursor to unknown screen {}", to_screen),
+        }
+    }
+
+    /// Ask all windows in `managed` with a matching `WM_CLASS` (either the instance or class
+    /// name) to close by sending them `WM_DELETE_WINDOW`, returning the ids of the windows that
+    /// were sent the request.
+    ///
+    /// Windows are removed from the [WindowManager] as normal once they are destroyed.
+    pub fn close_windows_matching(&self, class: &str, managed: &[WinId]) -> Vec<WinId> {
+        let matching: Vec<WinId> = managed
+            .iter()
+            .copied()
+            .filter(|&id| match self.api.get_prop(id, Atom::WmClass.as_ref()) {
+                Ok(Prop::UTF8String(classes)) => classes.iter().any(|c| c == class),
+                _ => false,
+            })
+            .collect();
+
+        if self.dry_run(|| format!("close_windows_matching({:?}) -> {:?}", class, matching)) {
+            return matching;
+        }
+
+        let del = Atom::WmDeleteWindow.as_ref();
+        for &id in matching.iter() {
+            if let Err(e) = self.api.send_client_event(id, del) {
+                error!("unable to close {}: {}", id, e);
+            }
+        }
+        self.api.flush();
+
+        matching
+    }
+
+    /// Store arbitrary layout state for the given workspace index in a property on the root
+    /// window so that it survives a restart of penrose (but not of the X server).
+    ///
+    /// The format of `state` is left up to the caller: it can be read back using
+    /// [load_layout_state][XcbConnection::load_layout_state].
+    pub fn save_layout_state(&self, wix: usize, state: &str) -> Result<()> {
+        let name = format!("{}{}", LAYOUT_STATE_PROP_PREFIX, wix);
+        self.set_string_prop(self.api.root(), &name, state)
+    }
+
+    /// Load layout state for the given workspace index previously stored using
+    /// [save_layout_state][XcbConnection::save_layout_state].
+    pub fn load_layout_state(&self, wix: usize) -> Option<String> {
+        let name = format!("{}{}", LAYOUT_STATE_PROP_PREFIX, wix);
+        match self.api.get_prop(self.api.root(), &name) {
+            Ok(Prop::UTF8String(strs)) => strs.into_iter().next(),
+            _ => None,
+        }
+    }
+
+    /// Record whether or not the target window has been manually floated by setting the
+    /// `_PENROSE_FLOATING` cardinal property on it.
+    ///
+    /// The property lives on the client window itself so it survives a restart of penrose and
+    /// can be read back using [window_marked_floating][XcbConnection::window_marked_floating]
+    /// when existing clients are picked up again.
+    pub fn mark_floating(&self, id: WinId, floating: bool) -> Result<()> {
+        self.set_cardinal_prop(id, FLOATING_PROP, &[floating as u32])
+    }
+
+    /// Whether the target window was marked as floating using
+    /// [mark_floating][XcbConnection::mark_floating].
+    ///
+    /// Returns `None` if the window has never been marked.
+    pub fn window_marked_floating(&self, id: WinId) -> Option<bool> {
+        match self.api.get_prop(id, FLOATING_PROP) {
+            Ok(Prop::Cardinal(val)) => Some(val != 0),
+            _ => None,
+        }
+    }
+
+    /// Whether or not the target window should be drawn with a border.
+    ///
+    /// Windows with a type in [NO_BORDER_WINDOW_TYPES] (splash screens, notifications) are always
+    /// positioned without a border. The window type is only checked the first time a given
+    /// window is queried: the result is cached until the window is destroyed.
+    pub fn should_have_border(&self, id: WinId) -> bool {
+        if let Some(&has_border) = self.has_border.borrow().get(&id) {
+            return has_border;
+        }
+
+        let has_border = !self.window_has_type_in(id, &self.no_border_types);
+        self.has_border.borrow_mut().insert(id, has_border);
+        has_border
+    }
+
+    /// Stack the target window above all others, including fullscreen clients, and keep it there.
+    ///
+    /// Windows raised in this way are restacked on top each time a client is made fullscreen or
+    /// raised so that they remain visible. This is done automatically for notifications and
+    /// windows with `_NET_WM_STATE_ABOVE` set when they request to be mapped.
+    pub fn raise_above_fullscreen(&self, id: WinId) {
+        if self.dry_run(|| format!("raise_above_fullscreen({})", id)) {
+            return;
+        }
+        {
+            let mut above = self.above_fullscreen.borrow_mut();
+            above.retain(|&w| w != id);
+            above.push(id);
+        }
+        self.api.stack_above_all(id);
+    }
+
+    // Re-raise windows that should stay above fullscreen clients. Destroyed windows are removed
+    // from tracking as their DestroyNotify events are received.
+    fn restack_above_fullscreen(&self) {
+        for &id in self.above_fullscreen.borrow().iter() {
+            self.api.stack_above_all(id);
+        }
+    }
+
+    fn wants_above_fullscreen(&self, id: WinId) -> bool {
+        let notification = self.api.known_atom(Atom::NetWindowTypeNotification);
+        self.window_has_type_in(id, &[notification])
+            || self.api.window_has_state(id, Atom::NetWmStateAbove)
+    }
+
+    // Clients that only set _NET_WM_WINDOW_TYPE_NORMAL are explicitly asking to be tiled
+    fn window_is_explicitly_normal(&self, id: WinId) -> bool {
+        match self.api.get_prop(id, Atom::NetWmWindowType.as_ref()) {
+            Ok(Prop::Atom(atoms)) => atoms == [Atom::NetWindowTypeNormal.as_ref()],
+            _ => false,
+        }
+    }
+
     /// Get a handle on the underlying [XCB Connection][::xcb::Connection] used by [Api]
     /// to communicate with the X server.
     pub fn xcb_connection(&self) -> &xcb::Connection {
@@ -104,6 +411,97 @@ impl XcbConnection {
     pub fn known_atoms(&self) -> &HashMap<Atom, u32> {
         &self.api.known_atoms()
     }
+
+    /// The [KeyCode]s that were grabbed by the most recent call to [XConn::grab_keys].
+    ///
+    /// Useful for debugging key bindings that are not firing by comparing against the bindings
+    /// in your config.
+    pub fn active_grabs(&self) -> Vec<KeyCode> {
+        self.grabbed_keys.borrow().clone()
+    }
+
+    /// The (button, modifier mask) pairs that were grabbed by the most recent call to
+    /// [XConn::grab_keys].
+    ///
+    /// Comparing this against a new set of mouse bindings allows for only regrabbing the buttons
+    /// that have changed.
+    pub fn active_button_grabs(&self) -> Vec<(u8, u16)> {
+        self.grabbed_buttons.borrow().clone()
+    }
+
+    /// Repaint the borders of the given windows using the colors from `config`.
+    ///
+    /// `focused` is given the focused border color and all windows in `others` are given the
+    /// unfocused border color. This is intended for use after reloading a color scheme, where
+    /// every managed window needs to be updated.
+    pub fn set_border_colors(&self, focused: WinId, others: &[WinId], config: &Config) {
+        if self.dry_run(|| format!("set_border_colors({}, {:?})", focused, others)) {
+            return;
+        }
+        self.api.set_border_colors(
+            focused,
+            others,
+            config.focused_border().rgb_u32(),
+            config.unfocused_border().rgb_u32(),
+        );
+    }
+
+    /// Record the last focused window for the given workspace index, clearing it if `id` is
+    /// None.
+    ///
+    /// This is updated automatically for the current workspace whenever a client is focused.
+    /// Combined with [workspace_focus][XcbConnection::workspace_focus] this allows for focus to
+    /// be tracked per workspace so that switching back to a workspace can restore the window
+    /// that was last focused there.
+    pub fn set_workspace_focus(&self, wix: usize, id: Option<WinId>) {
+        let mut focus = self.workspace_focus.borrow_mut();
+        match id {
+            Some(id) => focus.insert(wix, id),
+            None => focus.remove(&wix),
+        };
+    }
+
+    /// The last focused window recorded for the given workspace index using
+    /// [set_workspace_focus][XcbConnection::set_workspace_focus].
+    pub fn workspace_focus(&self, wix: usize) -> Option<WinId> {
+        self.workspace_focus.borrow().get(&wix).copied()
+    }
+
+    // Drop any per-window state we are holding for a window that has been destroyed
+    fn forget_window(&self, id: WinId) {
+        self.saved_geometry.borrow_mut().remove(&id);
+        self.floating_geometry.borrow_mut().remove(&id);
+        self.has_border.borrow_mut().remove(&id);
+        self.above_fullscreen.borrow_mut().retain(|&w| w != id);
+        self.workspace_focus.borrow_mut().retain(|_, w| *w != id);
+    }
+
+    /// Store the current geometry of the target window so that it can later be re-applied using
+    /// [restore_geometry][XcbConnection::restore_geometry].
+    ///
+    /// This is intended for preserving a window's position when it is temporarily moved, such as
+    /// when it is made fullscreen.
+    pub fn remember_geometry(&self, id: WinId) -> Result<()> {
+        let r = self.api.window_geometry(id)?;
+        self.saved_geometry.borrow_mut().insert(id, r);
+        Ok(())
+    }
+
+    /// Re-apply the geometry previously stored for the target window using
+    /// [remember_geometry][XcbConnection::remember_geometry].
+    ///
+    /// Returns false if there was no stored geometry for the window.
+    pub fn restore_geometry(&self, id: WinId) -> bool {
+        let r = match self.saved_geometry.borrow_mut().remove(&id) {
+            Some(r) => r,
+            None => return false,
+        };
+        if self.dry_run(|| format!("restore_geometry({}) -> {:?}", id, r)) {
+            return true;
+        }
+        self.api.configure_window(id, &[WinConfig::Position(r)]);
+        true
+    }
 }
 
 impl WindowManager<XcbConnection> {
@@ -126,6 +524,14 @@ impl XConn for XcbConnection {
     }
 
     fn init(&self) -> Result<()> {
+        let virtual_roots = self.api.virtual_roots();
+        if !virtual_roots.is_empty() {
+            warn!(
+                "virtual root windows detected ({:?}): windows inside them will not be managed",
+                virtual_roots
+            );
+        }
+
         Ok(self
             .api
             .set_window_attributes(self.api.root(), &[WinAttr::RootEventMask])
@@ -136,13 +542,41 @@ impl XConn for XcbConnection {
         self.api.flush()
     }
 
+    fn sync(&self) {
+        if let Err(e) = self.api.sync() {
+            warn!("unable to sync with the X server: {}", e);
+        }
+    }
+
+    // Destruction of our check window is reported via the SUBSTRUCTURE_NOTIFY mask on the root
+    // window so we only need to translate it here.
     fn wait_for_event(&self) -> Result<XEvent> {
-        Ok(self.api.wait_for_event()?)
+        match self.api.wait_for_event()? {
+            XEvent::Destroy { id } if id == self.check_win.get() => Ok(XEvent::CheckWindowLost),
+            XEvent::Destroy { id } => {
+                self.forget_window(id);
+                Ok(XEvent::Destroy { id })
+            }
+            XEvent::MapRequest { id, ignore } => {
+                // Only check new windows: windows we unmap ourselves are remapped directly
+                if !ignore && self.wants_above_fullscreen(id) {
+                    let mut above = self.above_fullscreen.borrow_mut();
+                    if !above.contains(&id) {
+                        above.push(id);
+                    }
+                }
+                Ok(XEvent::MapRequest { id, ignore })
+            }
+            event => Ok(event),
+        }
     }
 
     fn current_outputs(&self) -> Vec<Screen> {
         match self.api.current_screens() {
-            Ok(screens) => screens,
+            Ok(screens) => {
+                *self.outputs.borrow_mut() = screens.iter().map(|s| s.region(false)).collect();
+                screens
+            }
             Err(e) => panic!("{}", e),
         }
     }
@@ -152,6 +586,19 @@ impl XConn for XcbConnection {
     }
 
     fn position_window(&self, id: WinId, reg: Region, border: u32, stack_above: bool) {
+        if self.dry_run(|| {
+            format!(
+                "position_window({}, {:?}, {}, {})",
+                id, reg, border, stack_above
+            )
+        }) {
+            return;
+        }
+        let border = if self.should_have_border(id) {
+            border
+        } else {
+            0
+        };
         let mut data = vec![WinConfig::Position(reg), WinConfig::BorderPx(border)];
         if stack_above {
             data.push(WinConfig::StackAbove);
@@ -159,37 +606,110 @@ impl XConn for XcbConnection {
         self.api.configure_window(id, &data)
     }
 
+    fn latest_configure_request(&self, id: WinId) -> Option<Region> {
+        self.api.latest_configure_request(id)
+    }
+
     fn raise_window(&self, id: WinId) {
-        self.api.configure_window(id, &[WinConfig::StackAbove])
+        if self.dry_run(|| format!("raise_window({})", id)) {
+            return;
+        }
+        self.api.configure_window(id, &[WinConfig::StackAbove]);
+        self.restack_above_fullscreen();
     }
 
     fn mark_new_window(&self, id: WinId) {
+        if self.dry_run(|| format!("mark_new_window({})", id)) {
+            return;
+        }
         let data = &[WinAttr::ClientEventMask];
         // TODO: this should return the error once XConn is updated
         self.api.set_window_attributes(id, data).unwrap();
     }
 
     fn map_window(&self, id: WinId) {
+        if self.dry_run(|| format!("map_window({})", id)) {
+            return;
+        }
         self.api.map_window(id);
+        if self.above_fullscreen.borrow().contains(&id) {
+            self.api.stack_above_all(id);
+        }
     }
 
     fn unmap_window(&self, id: WinId) {
+        if self.dry_run(|| format!("unmap_window({})", id)) {
+            return;
+        }
+        self.api.unmap_window(id);
+    }
+
+    fn map_windows(&self, ids: &[WinId]) {
+        if self.dry_run(|| format!("map_windows({:?})", ids)) {
+            return;
+        }
+        self.api.map_windows(ids);
+        ids.iter()
+            .filter(|id| self.above_fullscreen.borrow().contains(id))
+            .for_each(|&id| self.api.stack_above_all(id));
+    }
+
+    fn unmap_windows(&self, ids: &[WinId]) {
+        if self.dry_run(|| format!("unmap_windows({:?})", ids)) {
+            return;
+        }
+        self.api.unmap_windows(ids);
+    }
+
+    fn iconify(&self, id: WinId) {
+        if self.dry_run(|| format!("iconify({})", id)) {
+            return;
+        }
+        self.api.set_wm_state(id, WindowState::Iconic, None);
+        if let Err(e) = self.api.set_window_state(id, Atom::NetWmStateHidden, true) {
+            warn!("unable to set _NET_WM_STATE_HIDDEN for {}: {}", id, e);
+        }
         self.api.unmap_window(id);
     }
 
+    fn deiconify(&self, id: WinId) {
+        if self.dry_run(|| format!("deiconify({})", id)) {
+            return;
+        }
+        self.api.set_wm_state(id, WindowState::Normal, None);
+        if let Err(e) = self.api.set_window_state(id, Atom::NetWmStateHidden, false) {
+            warn!("unable to clear _NET_WM_STATE_HIDDEN for {}: {}", id, e);
+        }
+    }
+
     fn send_client_event(&self, id: WinId, atom_name: &str) -> Result<()> {
+        if self.dry_run(|| format!("send_client_event({}, {:?})", id, atom_name)) {
+            return Ok(());
+        }
         Ok(self.api.send_client_event(id, atom_name)?)
     }
 
-    fn focused_client(&self) -> WinId {
-        self.api.focused_client().unwrap_or(0)
+    fn focused_client(&self) -> Option<WinId> {
+        match self.api.focused_client() {
+            Ok(id) if id == xcb::NONE || id == xcb::INPUT_FOCUS_POINTER_ROOT => None,
+            Ok(id) if id == self.api.root() => None,
+            Ok(id) => Some(id),
+            Err(_) => None,
+        }
     }
 
     fn focus_client(&self, id: WinId) {
+        self.set_workspace_focus(self.current_workspace.get(), Some(id));
+        if self.dry_run(|| format!("focus_client({})", id)) {
+            return;
+        }
         self.api.mark_focused_window(id);
     }
 
     fn set_client_border_color(&self, id: WinId, color: Color) {
+        if self.dry_run(|| format!("set_client_border_color({}, {:?})", id, color)) {
+            return;
+        }
         let data = &[WinAttr::BorderColor(color.rgb_u32())];
         // TODO: this should return the error once XConn is updated
         if let Err(e) = self.api.set_window_attributes(id, data) {
@@ -198,34 +718,56 @@ impl XConn for XcbConnection {
     }
 
     fn toggle_client_fullscreen(&self, id: WinId, client_is_fullscreen: bool) {
+        if self.dry_run(|| format!("toggle_client_fullscreen({}, {})", id, client_is_fullscreen)) {
+            return;
+        }
         let data = if client_is_fullscreen {
+            self.restore_geometry(id);
             0
         } else {
+            if let Err(e) = self.remember_geometry(id) {
+                warn!(
+                    "unable to store geometry for {} before fullscreen: {}",
+                    id, e
+                );
+            }
             self.api.known_atom(Atom::NetWmStateFullscreen)
         };
 
         self.api
             .replace_prop(id, Atom::NetWmState, PropVal::Atom(&[data]));
+        self.restack_above_fullscreen();
     }
 
     fn grab_keys(&self, key_bindings: &KeyBindings<Self>, mouse_bindings: &MouseBindings<Self>) {
-        self.api.grab_keys(&key_bindings.keys().collect::<Vec<_>>());
-        self.api.grab_mouse_buttons(
-            &mouse_bindings
-                .keys()
-                .map(|(_, state)| state)
-                .collect::<Vec<_>>(),
-        );
+        if self.dry_run(|| "grab_keys(..)".into()) {
+            warn!("dry run: key and mouse bindings are disabled, send SIGINT or SIGTERM to exit");
+            return;
+        }
+        let keys: Vec<_> = key_bindings.keys().collect();
+        self.api.grab_keys(&keys);
+        *self.grabbed_keys.borrow_mut() = keys.into_iter().copied().collect();
+        let states: Vec<_> = mouse_bindings.keys().map(|(_, state)| state).collect();
+        self.api.grab_mouse_buttons(&states);
+        *self.grabbed_buttons.borrow_mut() =
+            states.iter().map(|s| (s.button(), s.mask())).collect();
         self.flush();
     }
 
     fn set_wm_properties(&self, workspaces: &[&str]) {
+        if self.dry_run(|| format!("set_wm_properties({:?})", workspaces)) {
+            return;
+        }
+        // Hold the server while we set everything up so that other clients never see a
+        // partially initialised set of EWMH properties.
+        self.api.grab_server();
         let root = self.api.root();
-        for &win in &[self.check_win, root] {
+        let check_win = self.check_win.get();
+        for &win in &[check_win, root] {
             self.api.replace_prop(
                 win,
                 Atom::NetSupportingWmCheck,
-                PropVal::Window(&[self.check_win]),
+                PropVal::Window(&[check_win]),
             );
             let val = PropVal::Str(WM_NAME);
             self.api.replace_prop(win, Atom::WmName, val);
@@ -241,9 +783,23 @@ impl XConn for XcbConnection {
         self.api.replace_prop(root, Atom::NetSupported, prop);
         self.update_desktops(workspaces);
         self.api.delete_prop(root, Atom::NetClientList);
+        self.api.ungrab_server();
+    }
+
+    fn recreate_check_window(&self) -> Result<()> {
+        if self.dry_run(|| "recreate_check_window()".into()) {
+            return Ok(());
+        }
+        let r = Region::new(0, 0, 1, 1);
+        self.check_win
+            .set(self.api.create_window(WinType::CheckWin, r, false)?);
+        Ok(())
     }
 
     fn update_desktops(&self, workspaces: &[&str]) {
+        if self.dry_run(|| format!("update_desktops({:?})", workspaces)) {
+            return;
+        }
         let root = self.api.root();
         self.api.replace_prop(
             root,
@@ -258,6 +814,9 @@ impl XConn for XcbConnection {
     }
 
     fn update_known_clients(&self, clients: &[WinId]) {
+        if self.dry_run(|| format!("update_known_clients({:?})", clients)) {
+            return;
+        }
         self.api.replace_prop(
             self.api.root(),
             Atom::NetClientList,
@@ -271,6 +830,11 @@ impl XConn for XcbConnection {
     }
 
     fn set_current_workspace(&self, wix: usize) {
+        // Local state is still tracked in dry run mode so that workspace focus stays accurate
+        self.current_workspace.set(wix);
+        if self.dry_run(|| format!("set_current_workspace({})", wix)) {
+            return;
+        }
         self.api.replace_prop(
             self.api.root(),
             Atom::NetCurrentDesktop,
@@ -279,11 +843,17 @@ impl XConn for XcbConnection {
     }
 
     fn set_root_window_name(&self, root_name: &str) {
+        if self.dry_run(|| format!("set_root_window_name({:?})", root_name)) {
+            return;
+        }
         self.api
             .replace_prop(self.api.root(), Atom::WmName, PropVal::Str(root_name));
     }
 
     fn set_client_workspace(&self, id: WinId, workspace: usize) {
+        if self.dry_run(|| format!("set_client_workspace({}, {})", id, workspace)) {
+            return;
+        }
         self.api.replace_prop(
             id,
             Atom::NetWmDesktop,
@@ -291,7 +861,15 @@ impl XConn for XcbConnection {
         );
     }
 
+    // Precedence for determining if a window should float is:
+    //   - windows explicitly setting only _NET_WM_WINDOW_TYPE_NORMAL are always tiled
+    //   - windows with a WM_CLASS in floating_classes are floated
+    //   - windows with a _NET_WM_WINDOW_TYPE in AUTO_FLOAT_WINDOW_TYPES are floated
     fn window_should_float(&self, id: WinId, floating_classes: &[&str]) -> bool {
+        if self.window_is_explicitly_normal(id) {
+            return false;
+        }
+
         if let Ok(Prop::UTF8String(strs)) = self.get_prop(id, Atom::WmClass.as_ref()) {
             if strs.iter().any(|c| floating_classes.contains(&c.as_ref())) {
                 return true;
@@ -304,11 +882,31 @@ impl XConn for XcbConnection {
         self.api.window_is_managed(id)
     }
 
+    // Compared against the outputs from the last call to current_outputs rather than querying
+    // RandR again: the WindowManager refreshes those whenever the screen layout changes.
+    fn is_fullscreen_override(&self, id: WinId) -> bool {
+        if !self.api.window_is_override_redirect(id) {
+            return false;
+        }
+
+        match self.api.window_geometry(id) {
+            Ok(r) => self.outputs.borrow().iter().any(|o| r.contains(o)),
+            Err(_) => false,
+        }
+    }
+
+    fn window_wants_maximized(&self, id: WinId) -> (bool, bool) {
+        self.api.window_wants_maximized(id)
+    }
+
     fn window_geometry(&self, id: WinId) -> Result<Region> {
         Ok(self.api.window_geometry(id)?)
     }
 
     fn warp_cursor(&self, win_id: Option<WinId>, screen: &Screen) {
+        if self.dry_run(|| format!("warp_cursor({:?}, {:?})", win_id, screen.region(true))) {
+            return;
+        }
         let (x, y, id) = match win_id {
             Some(id) => {
                 let (_, _, w, h) = match self.window_geometry(id) {
@@ -330,7 +928,19 @@ impl XConn for XcbConnection {
     }
 
     fn query_for_active_windows(&self) -> Vec<WinId> {
-        match self.api.current_clients() {
+        // Make sure that our root event mask is active before scanning so that any windows
+        // created after this point will arrive as map requests. See Api::sync for details.
+        // Grabbing the server blocks all other clients so we skip it when doing a dry run.
+        let grab = !self.dry_run(|| "grab_server()".into());
+        if grab {
+            self.api.grab_server();
+        }
+        let clients = self.api.sync().and_then(|_| self.api.current_clients());
+        if grab {
+            self.api.ungrab_server();
+        }
+
+        match clients {
             Err(_) => Vec::new(),
             Ok(ids) => ids
                 .into_iter()
@@ -339,6 +949,10 @@ impl XConn for XcbConnection {
         }
     }
 
+    fn mapped_windows(&self) -> Vec<WinId> {
+        self.api.mapped_windows()
+    }
+
     fn get_prop(&self, id: WinId, name: &str) -> Result<Prop> {
         Ok(self.api.get_prop(id, name)?)
     }
@@ -351,13 +965,42 @@ impl XConn for XcbConnection {
         Ok(self.api.atom(atom)?)
     }
 
+    fn set_cardinal_prop(&self, id: WinId, name: &str, values: &[u32]) -> Result<()> {
+        if self.dry_run(|| format!("set_cardinal_prop({}, {:?}, {:?})", id, name, values)) {
+            return Ok(());
+        }
+        Ok(self.api.set_cardinal_prop(id, name, values)?)
+    }
+
+    fn set_string_prop(&self, id: WinId, name: &str, value: &str) -> Result<()> {
+        if self.dry_run(|| format!("set_string_prop({}, {:?}, {:?})", id, name, value)) {
+            return Ok(());
+        }
+        Ok(self.api.set_string_prop(id, name, value)?)
+    }
+
     // - Release all of the keybindings we are holding on to
+    // - free any graphics contexts and fonts we have open
     // - destroy the check window
     // - mark ourselves as no longer being the active root window
     fn cleanup(&self) {
+        if self.dry_run(|| "cleanup()".into()) {
+            return;
+        }
         self.api.ungrab_keys();
+        self.grabbed_keys.borrow_mut().clear();
         self.api.ungrab_mouse_buttons();
-        self.api.destroy_window(self.check_win);
+        self.grabbed_buttons.borrow_mut().clear();
+        self.api.free_all_gcs();
+        self.api.close_fonts();
+        self.api.destroy_window(self.check_win.get());
         self.api.delete_prop(self.api.root(), Atom::NetActiveWindow);
+
+        if self.unmap_override_redirect_on_cleanup {
+            match self.api.mapped_override_redirect_windows() {
+                Ok(ids) => self.api.unmap_windows(&ids),
+                Err(e) => warn!("unable to query override-redirect windows: {}", e),
+            }
+        }
     }
 }
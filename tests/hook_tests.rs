@@ -70,27 +70,27 @@ penrose::test_cases! {
     ]);
     case: client_added_to_workspace => ("client_added_to_workspace", 2, vec![
         XEvent::MapRequest { id: 1, ignore: false },
-        XEvent::KeyPress(common::CLIENT_TO_WORKSPACE_CODE)
+        XEvent::KeyPress { code: common::CLIENT_TO_WORKSPACE_CODE, root: 0 }
     ]);
     case: event_handled => ("event_handled", 2, vec![XEvent::ScreenChange]);
     case: focus_change => ("focus_change", 3, vec![
         XEvent::MapRequest { id: 1, ignore: false },
         XEvent::MapRequest { id: 2, ignore: false },
-        XEvent::KeyPress(common::FOCUS_CHANGE_CODE)
+        XEvent::KeyPress { code: common::FOCUS_CHANGE_CODE, root: 0 }
     ]);
-    case: layout_applied => ("layout_applied", 3, vec![XEvent::KeyPress(common::LAYOUT_CHANGE_CODE)]);
-    case: layout_change => ("layout_change", 1, vec![XEvent::KeyPress(common::LAYOUT_CHANGE_CODE)]);
+    case: layout_applied => ("layout_applied", 3, vec![XEvent::KeyPress { code: common::LAYOUT_CHANGE_CODE, root: 0 }]);
+    case: layout_change => ("layout_change", 1, vec![XEvent::KeyPress { code: common::LAYOUT_CHANGE_CODE, root: 0 }]);
     case: new_client => ("new_client", 1, vec![XEvent::MapRequest { id: 1, ignore: false}]);
     case: randr_notify => ("randr_notify", 1, vec![XEvent::RandrNotify]);
     case: remove_client => ("remove_client", 1, vec![
         XEvent::MapRequest { id: 1, ignore: false},
-        XEvent::KeyPress(common::KILL_CLIENT_CODE)
+        XEvent::KeyPress { code: common::KILL_CLIENT_CODE, root: 0 }
     ]);
-    case: screen_change => ("screen_change", 1, vec![XEvent::KeyPress(common::SCREEN_CHANGE_CODE)]);
+    case: screen_change => ("screen_change", 1, vec![XEvent::KeyPress { code: common::SCREEN_CHANGE_CODE, root: 0 }]);
     case: screens_updated => ("screens_updated", 1, vec![XEvent::RandrNotify]);
     case: startup => ("startup", 1, vec![]);
-    case: workspace_change => ("workspace_change", 1, vec![XEvent::KeyPress(common::WORKSPACE_CHANGE_CODE)]);
-    case: workspaces_updated => ("workspaces_updated", 1, vec![XEvent::KeyPress(common::ADD_WORKSPACE_CODE)]);
+    case: workspace_change => ("workspace_change", 1, vec![XEvent::KeyPress { code: common::WORKSPACE_CHANGE_CODE, root: 0 }]);
+    case: workspaces_updated => ("workspaces_updated", 1, vec![XEvent::KeyPress { code: common::ADD_WORKSPACE_CODE, root: 0 }]);
 
     body: {
         let calls = Rc::new(RefCell::new(vec![]));
@@ -100,7 +100,7 @@ penrose::test_cases! {
         })];
 
         let mut events = events;
-        events.push(XEvent::KeyPress(common::EXIT_CODE));
+        events.push(XEvent::KeyPress { code: common::EXIT_CODE, root: 0 });
 
         let screens = vec![common::simple_screen(0), common::simple_screen(1)];
         let conn = MockXConn::new(screens, events, vec![]);
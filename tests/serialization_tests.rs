@@ -46,7 +46,10 @@ impl StubXConn for EarlyExitConn {
     fn mock_wait_for_event(&self) -> penrose::Result<XEvent> {
         let mut remaining = self.events.replace(vec![]);
         if remaining.is_empty() {
-            return Ok(XEvent::KeyPress(common::EXIT_CODE));
+            return Ok(XEvent::KeyPress {
+                code: common::EXIT_CODE,
+                root: 0,
+            });
         }
         let next = remaining.remove(0);
         self.events.set(remaining);
@@ -83,7 +86,10 @@ fn get_seeded_wm(valid_clients: bool) -> WindowManager<EarlyExitConn> {
                 id: 1,
                 ignore: false,
             },
-            XEvent::KeyPress(common::WORKSPACE_CHANGE_CODE),
+            XEvent::KeyPress {
+                code: common::WORKSPACE_CHANGE_CODE,
+                root: 0,
+            },
             XEvent::MapRequest {
                 id: 2,
                 ignore: false,
@@ -92,7 +98,10 @@ fn get_seeded_wm(valid_clients: bool) -> WindowManager<EarlyExitConn> {
                 id: 3,
                 ignore: false,
             },
-            XEvent::KeyPress(common::FOCUS_CHANGE_CODE),
+            XEvent::KeyPress {
+                code: common::FOCUS_CHANGE_CODE,
+                root: 0,
+            },
         ],
     );
 